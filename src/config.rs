@@ -0,0 +1,151 @@
+//! User settings, read from the JSON file passed with `--config`
+//!
+//! ```json
+//! {
+//!     "defaults": { "iterator": "list" },
+//!     "items": {
+//!         "macroquad::experimental::scene::find_nodes_by_type": { "iterator": "resource" }
+//!     }
+//! }
+//! ```
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Settings for every item
+    pub defaults: ItemConfig,
+    /// Settings for single items by Rust path, overriding the defaults
+    pub items: HashMap<String, ItemConfig>,
+}
+
+impl Config {
+    /// The settings for the item at `rust_path`, e.g. `macroquad::ui::root_ui`
+    pub fn item(&self, rust_path: &str) -> ItemConfig {
+        let defaults = &self.defaults;
+        match self.items.get(rust_path) {
+            Some(item) => ItemConfig {
+                iterator: item.iterator.or(defaults.iterator),
+                constant_values: item.constant_values.or(defaults.constant_values),
+                helpers: item.helpers.clone().or(defaults.helpers.clone()),
+                flags: item.flags.or(defaults.flags),
+                discriminants: item.discriminants.or(defaults.discriminants),
+                deprecated: item.deprecated.or(defaults.deprecated),
+                since: item.since.clone().or(defaults.since.clone()),
+                method_names: item.method_names.clone().or(defaults.method_names.clone()),
+                recursive: item.recursive.or(defaults.recursive),
+            },
+            None => defaults.clone(),
+        }
+    }
+}
+
+/// Settings that can be set for every item or for single items
+///
+/// `None` means the setting is left to its default.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ItemConfig {
+    pub iterator: Option<IteratorMode>,
+    /// Whether the value of a constant is written in a WIT doc comment above
+    /// its getter
+    pub constant_values: Option<bool>,
+    /// Functions to generate for the std traits a type implements
+    pub helpers: Option<Vec<Helper>>,
+    /// Whether a struct is exported as WIT `flags`, overriding the detection
+    /// of `bitflags!`-style structs
+    pub flags: Option<bool>,
+    /// Whether C-like enums get functions converting from and to their
+    /// discriminants
+    pub discriminants: Option<bool>,
+    pub deprecated: Option<DeprecatedMode>,
+    /// The version in the `@since` gate of items, or `"crate"` for the
+    /// version of the crate
+    pub since: Option<String>,
+    /// WIT names of the methods of a resource, by `method` for inherent
+    /// methods or `Trait::method` for trait methods
+    pub method_names: Option<HashMap<String, String>>,
+    pub recursive: Option<RecursiveMode>,
+}
+
+impl ItemConfig {
+    pub fn iterator(&self) -> IteratorMode {
+        self.iterator.unwrap_or_default()
+    }
+
+    pub fn constant_values(&self) -> bool {
+        self.constant_values.unwrap_or(false)
+    }
+
+    pub fn discriminants(&self) -> bool {
+        self.discriminants.unwrap_or(false)
+    }
+
+    pub fn deprecated(&self) -> DeprecatedMode {
+        self.deprecated.unwrap_or_default()
+    }
+
+    pub fn helpers(&self) -> &[Helper] {
+        self.helpers.as_deref().unwrap_or_default()
+    }
+
+    pub fn recursive(&self) -> RecursiveMode {
+        self.recursive.unwrap_or_default()
+    }
+}
+
+/// How `impl Iterator<Item = T>` return types are exported
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum IteratorMode {
+    /// Collected eagerly into a `list<t>`
+    #[default]
+    List,
+    /// A resource with a `next: func() -> option<t>` method
+    Resource,
+}
+
+/// How structs that contain themselves are exported, as WIT types can't
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecursiveMode {
+    /// A resource, so the value stays in the host
+    #[default]
+    Resource,
+    /// A record of a `list` of entries, which refer to each other by index
+    Arena,
+}
+
+/// What happens to `#[deprecated]` items
+#[derive(Clone, Copy, Default, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeprecatedMode {
+    /// Not exported
+    Skip,
+    /// Exported with a `@deprecated(version = x.y.z)` WIT annotation, or a
+    /// doc note if the deprecation has no `since` version
+    Annotate,
+    /// Exported with a note in their doc comment
+    #[default]
+    Note,
+}
+
+/// A function for a std trait a type implements, named after the type, e.g.
+/// `color-to-string`
+#[derive(Clone, Copy, Deserialize, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum Helper {
+    /// `Display`, as `to-string: func(value: t) -> string`
+    ToString,
+    /// `Debug`, as `debug-string: func(value: t) -> string`
+    DebugString,
+    /// `Default`, as `default: func() -> t`
+    Default,
+    /// `Clone` of resources, as `clone: func(value: t) -> t`
+    Clone,
+    /// `FromStr`, as `parse: func(s: string) -> result<t, string>`
+    Parse,
+}
@@ -0,0 +1,506 @@
+//! Guest-side wrappers over the bindings `wit_bindgen::generate!` makes of the
+//! generated WIT, so guests can use the library under its Rust names
+//!
+//! Like the host code, the wrappers refer to the bindings through a `wit`
+//! module re-exporting the types and functions of every interface, which the
+//! crate skeleton of [`GuestCode::crate_files`] declares.
+//!
+//! `wit-bindgen` already gives resources their methods. The wrappers add a
+//! module per Rust module re-exporting its types, functions and constants
+//! under their Rust names, and implement the operator, conversion and helper
+//! traits the library implements by calling the functions they are exported as.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+    path::PathBuf,
+};
+
+use crate::{
+    config,
+    host::{bindgen_name, function_name},
+    indented, Record, Variant, WitFunction, WitType, WitTypeKind,
+};
+
+/// The version of `wit-bindgen` the crate skeleton depends on
+const WIT_BINDGEN_VERSION: &str = "0.36";
+
+#[derive(Default)]
+pub struct GuestCode {
+    /// Items re-exported from `wit`, e.g. `Color` or `white as WHITE`, by
+    /// Rust module path
+    modules: BTreeMap<Vec<String>, Vec<String>>,
+    /// Records, variants, flags and type aliases by WIT name, to tell how
+    /// they are passed
+    definitions: HashMap<String, WitType>,
+    /// Operator traits with their method, and the functions applying them
+    operators: Vec<(String, String, WitFunction)>,
+    /// Conversion functions, and whether they can fail
+    conversions: Vec<(WitFunction, bool)>,
+    helpers: Vec<(config::Helper, WitFunction)>,
+    /// Getters of associated constants, with the constant names, by the WIT
+    /// name of their type
+    associated: BTreeMap<String, Vec<(String, WitFunction)>>,
+}
+
+impl GuestCode {
+    /// Re-export the `wit` type `wit_name` as the type at `rust_path`
+    pub fn type_(&mut self, rust_path: &str, wit_name: &str) {
+        self.re_export(rust_path, &bindgen_name(wit_name));
+    }
+
+    /// Re-export the `wit` function of `wit` as the function, constant or
+    /// static at `rust_path`, e.g. `WHITE()` for `const WHITE: Color`
+    pub fn function(&mut self, rust_path: &str, wit: &WitFunction) {
+        self.re_export(rust_path, &function_name(&wit.name));
+    }
+
+    /// Keep a type definition, as `wit-bindgen` passes types containing
+    /// strings or lists by reference
+    pub fn definition(&mut self, ty: &WitType) {
+        match &ty.kind {
+            WitTypeKind::Record(Record { name, .. })
+            | WitTypeKind::Variant(Variant { name, .. })
+            | WitTypeKind::Flags(crate::Flags { name, .. })
+            | WitTypeKind::TypeAlias(crate::TypeAlias { name, .. }) => {
+                self.definitions.insert(name.clone(), ty.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// `impl core::ops::{trait_}` calling the function `wit` applying it
+    pub fn operator(&mut self, trait_: &str, method: &str, wit: &WitFunction) {
+        self.operators
+            .push((trait_.to_string(), method.to_string(), wit.clone()));
+    }
+
+    /// `impl From` or `impl TryFrom` calling the conversion function `wit`
+    pub fn conversion(&mut self, wit: &WitFunction, fallible: bool) {
+        self.conversions.push((wit.clone(), fallible));
+    }
+
+    /// The std trait impl calling the helper function `wit`
+    pub fn helper(&mut self, helper: config::Helper, wit: &WitFunction) {
+        self.helpers.push((helper, wit.clone()));
+    }
+
+    /// An associated function of the type `wit_name` named after the
+    /// associated constant `name`, calling its getter `wit`
+    pub fn associated(&mut self, wit_name: &str, name: &str, wit: &WitFunction) {
+        self.associated
+            .entry(wit_name.to_string())
+            .or_default()
+            .push((name.to_string(), wit.clone()));
+    }
+
+    fn re_export(&mut self, rust_path: &str, bindgen: &str) {
+        let mut path = rust_path
+            .split("::")
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        let name = path.pop().expect("items have a name");
+        let item = if name == bindgen {
+            name
+        } else {
+            format!("{bindgen} as {name}")
+        };
+        self.modules.entry(path).or_default().push(item);
+    }
+
+    /// The files of a guest crate for the library `library` of the package
+    /// `namespace:library@version`, with `wit` as its WIT and the wrappers as
+    /// the `library` module, for a `world` importing the `interfaces`
+    pub fn crate_files(
+        &self,
+        namespace: &str,
+        library: &str,
+        version: &str,
+        interfaces: &[String],
+        wit: &str,
+        world: &str,
+    ) -> Vec<(PathBuf, String)> {
+        let module = function_name(library);
+        let mut manifest = String::new();
+        writeln!(manifest, "[package]").unwrap();
+        writeln!(
+            manifest,
+            "name = \"{}-guest\"",
+            crate::names::ident(library)
+        )
+        .unwrap();
+        writeln!(manifest, "version = \"0.1.0\"").unwrap();
+        writeln!(manifest, "edition = \"2021\"\n").unwrap();
+        writeln!(manifest, "[lib]").unwrap();
+        writeln!(manifest, "crate-type = [\"cdylib\"]\n").unwrap();
+        writeln!(manifest, "[dependencies]").unwrap();
+        writeln!(manifest, "wit-bindgen = \"{WIT_BINDGEN_VERSION}\"").unwrap();
+
+        let mut lib = String::new();
+        writeln!(lib, "wit_bindgen::generate!({{").unwrap();
+        writeln!(lib, "    path: \"wit\",").unwrap();
+        writeln!(lib, "    world: \"{world}\",").unwrap();
+        writeln!(lib, "}});\n").unwrap();
+        writeln!(lib, "/// The bindings of every interface, for the wrappers").unwrap();
+        writeln!(lib, "mod wit {{").unwrap();
+        for interface in interfaces {
+            writeln!(
+                lib,
+                "    pub use crate::{}::{}::{}::*;",
+                function_name(namespace),
+                function_name(library),
+                function_name(interface)
+            )
+            .unwrap();
+        }
+        writeln!(lib, "}}\n").unwrap();
+        writeln!(lib, "/// The library under its Rust names").unwrap();
+        writeln!(lib, "pub mod {module};\n").unwrap();
+        writeln!(
+            lib,
+            "// implement the exports of `{world}` here, and `export!` the implementation"
+        )
+        .unwrap();
+
+        vec![
+            (PathBuf::from("Cargo.toml"), manifest),
+            (
+                PathBuf::from(format!("wit/{}.wit", crate::names::ident(library))),
+                format!(
+                    "package {}:{}@{version};\n\n{wit}\n",
+                    crate::names::ident(namespace),
+                    crate::names::ident(library)
+                ),
+            ),
+            (PathBuf::from("src/lib.rs"), lib),
+            (
+                PathBuf::from(format!("src/{module}.rs")),
+                self.print().to_string(),
+            ),
+        ]
+    }
+
+    /// The wrappers, as the contents of the library's module
+    pub fn print(&self) -> impl std::fmt::Display {
+        // every module, including those that only have submodules
+        let mut modules = BTreeSet::new();
+        for path in self.modules.keys() {
+            for len in 1..=path.len() {
+                modules.insert(&path[..len]);
+            }
+        }
+        let mut f = String::new();
+        if let Some(root) = modules.first().copied() {
+            self.print_module(&mut f, root, &modules);
+        }
+
+        // impl headers already written, as the same Rust impl can come from
+        // several WIT functions, e.g. `Add<Vec2>` and `Add<&Vec2>`
+        let mut headers = BTreeSet::new();
+
+        for (name, getters) in &self.associated {
+            let ty = guest_type(&WitType::builtin(WitTypeKind::Named(name.clone())));
+            let mut body = String::new();
+            for (idx, (constant, getter)) in getters.iter().enumerate() {
+                let Some(output) = &getter.output else {
+                    continue;
+                };
+                if idx > 0 {
+                    writeln!(body).unwrap();
+                }
+                writeln!(body, "#[allow(non_snake_case)]").unwrap();
+                writeln!(body, "pub fn {constant}() -> {} {{", guest_type(output)).unwrap();
+                writeln!(body, "    crate::wit::{}()", function_name(&getter.name)).unwrap();
+                write!(body, "}}").unwrap();
+            }
+            if self.is_local(&WitType::builtin(WitTypeKind::Named(name.clone()))) {
+                impl_block(&mut f, &mut headers, format!("impl {ty}"), body);
+            }
+        }
+
+        for (trait_, method, wit) in &self.operators {
+            let (Some((_, lhs)), Some(output)) = (wit.params.first(), &wit.output) else {
+                continue;
+            };
+            let rhs = wit.params.get(1).map(|(_, rhs)| rhs);
+            // `bitflags!` already implements the bit operators of flags
+            if !(self.is_local(lhs) || rhs.is_some_and(|rhs| self.is_local(rhs)))
+                || self.is_flags(lhs)
+            {
+                continue;
+            }
+            let Some(args) = std::iter::once(self.arg("self", lhs))
+                .chain(rhs.map(|rhs| self.arg("rhs", rhs)))
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let header = match rhs {
+                Some(rhs) => format!(
+                    "impl core::ops::{trait_}<{}> for {}",
+                    guest_type(rhs),
+                    guest_type(lhs)
+                ),
+                None => format!("impl core::ops::{trait_} for {}", guest_type(lhs)),
+            };
+            let mut body = String::new();
+            writeln!(body, "type Output = {};\n", guest_type(output)).unwrap();
+            match rhs {
+                Some(rhs) => writeln!(
+                    body,
+                    "fn {method}(self, rhs: {}) -> Self::Output {{",
+                    guest_type(rhs)
+                )
+                .unwrap(),
+                None => writeln!(body, "fn {method}(self) -> Self::Output {{").unwrap(),
+            }
+            writeln!(
+                body,
+                "    crate::wit::{}({})",
+                function_name(&wit.name),
+                args.join(", ")
+            )
+            .unwrap();
+            write!(body, "}}").unwrap();
+            impl_block(&mut f, &mut headers, header, body);
+        }
+
+        // `From` before `TryFrom`, as `From` implies `TryFrom`
+        let mut conversions = self.conversions.iter().collect::<Vec<_>>();
+        conversions.sort_by_key(|(_, fallible)| *fallible);
+        for (wit, fallible) in conversions {
+            let (Some((_, from)), Some(output)) = (wit.params.first(), &wit.output) else {
+                continue;
+            };
+            let (to, error) = match (&output.kind, fallible) {
+                (WitTypeKind::Result { ok: Some(ok), err }, true) => (
+                    ok.as_ref(),
+                    err.as_deref().map_or("()".to_string(), guest_type),
+                ),
+                (_, true) => continue,
+                (_, false) => (output, String::new()),
+            };
+            let (from_type, to_type) = (guest_type(from), guest_type(to));
+            if from_type == to_type || !(self.is_local(from) || self.is_local(to)) {
+                continue;
+            }
+            let Some(arg) = self.arg("value", from) else {
+                continue;
+            };
+            let call = format!("crate::wit::{}({arg})", function_name(&wit.name));
+            let mut body = String::new();
+            if *fallible {
+                if headers.contains(&format!("impl From<{from_type}> for {to_type}")) {
+                    continue;
+                }
+                writeln!(body, "type Error = {error};\n").unwrap();
+                writeln!(
+                    body,
+                    "fn try_from(value: {from_type}) -> Result<Self, Self::Error> {{"
+                )
+                .unwrap();
+                writeln!(body, "    {call}").unwrap();
+                write!(body, "}}").unwrap();
+                impl_block(
+                    &mut f,
+                    &mut headers,
+                    format!("impl TryFrom<{from_type}> for {to_type}"),
+                    body,
+                );
+            } else {
+                writeln!(body, "fn from(value: {from_type}) -> Self {{").unwrap();
+                writeln!(body, "    {call}").unwrap();
+                write!(body, "}}").unwrap();
+                impl_block(
+                    &mut f,
+                    &mut headers,
+                    format!("impl From<{from_type}> for {to_type}"),
+                    body,
+                );
+            }
+        }
+
+        for (helper, wit) in &self.helpers {
+            let function = function_name(&wit.name);
+            let (header, body) = match (helper, wit.params.first(), &wit.output) {
+                (config::Helper::ToString, Some((_, ty)), _)
+                    if self.is_local(ty) || matches!(ty.kind, WitTypeKind::Borrow(_)) =>
+                {
+                    // resources are borrowed, so their type is the one of the
+                    // owned handle
+                    let (arg, ty) = match &ty.kind {
+                        WitTypeKind::Borrow(name) => ("self", format!("crate::wit::{}", bindgen_name(name))),
+                        _ if self.by_ref(ty) => ("self", guest_type(ty)),
+                        _ => ("*self", guest_type(ty)),
+                    };
+                    (
+                        format!("impl core::fmt::Display for {ty}"),
+                        format!(
+                            "fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {{\n    f.write_str(&crate::wit::{function}({arg}))\n}}"
+                        ),
+                    )
+                }
+                (config::Helper::Default, _, Some(ty)) if self.is_local(ty) => (
+                    format!("impl Default for {}", guest_type(ty)),
+                    format!("fn default() -> Self {{\n    crate::wit::{function}()\n}}"),
+                ),
+                (
+                    config::Helper::Parse,
+                    _,
+                    Some(WitType {
+                        kind: WitTypeKind::Result { ok: Some(ty), .. },
+                        ..
+                    }),
+                ) if self.is_local(ty) => (
+                    format!("impl core::str::FromStr for {}", guest_type(ty)),
+                    format!(
+                        "type Err = String;\n\nfn from_str(s: &str) -> Result<Self, Self::Err> {{\n    crate::wit::{function}(s)\n}}"
+                    ),
+                ),
+                // `wit-bindgen` already implements `Debug` and `Clone`
+                _ => continue,
+            };
+            impl_block(&mut f, &mut headers, header, body);
+        }
+        f
+    }
+
+    fn print_module(&self, f: &mut String, path: &[String], modules: &BTreeSet<&[String]>) {
+        let mut blocks = Vec::new();
+        let mut items = self.modules.get(path).cloned().unwrap_or_default();
+        items.sort();
+        items.dedup();
+        match items.as_slice() {
+            [] => {}
+            [item] => blocks.push(format!("pub use crate::wit::{item};")),
+            _ => blocks.push(format!("pub use crate::wit::{{{}}};", items.join(", "))),
+        }
+        for module in modules
+            .iter()
+            .filter(|module| module.len() == path.len() + 1 && module.starts_with(path))
+        {
+            let mut inner = String::new();
+            self.print_module(&mut inner, module, modules);
+            let inner = inner
+                .lines()
+                .map(|line| match line {
+                    "" => String::new(),
+                    line => format!("    {line}"),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            blocks.push(format!("pub mod {} {{\n{inner}\n}}", module[path.len()]));
+        }
+        writeln!(f, "{}", blocks.join("\n\n")).unwrap();
+    }
+
+    /// Whether `wit-bindgen` passes a value of type `ty` to an imported
+    /// function by reference, i.e. when it contains strings or lists
+    fn by_ref(&self, ty: &WitType) -> bool {
+        match &ty.kind {
+            WitTypeKind::String | WitTypeKind::List(_) => true,
+            WitTypeKind::Option(ty) => self.by_ref(ty),
+            WitTypeKind::Tuple(types) => types.iter().any(|ty| self.by_ref(ty)),
+            WitTypeKind::Result { ok, err } => ok.iter().chain(err).any(|ty| self.by_ref(ty)),
+            WitTypeKind::Record(record) => record.fields.iter().any(|(_, ty)| self.by_ref(ty)),
+            WitTypeKind::Variant(variant) => variant
+                .cases
+                .iter()
+                .any(|(_, payload)| payload.as_ref().is_some_and(|ty| self.by_ref(ty))),
+            WitTypeKind::TypeAlias(alias) => self.by_ref(&alias.ty),
+            WitTypeKind::Named(name) => {
+                self.definitions.get(name).is_some_and(|ty| self.by_ref(ty))
+            }
+            _ => false,
+        }
+    }
+
+    /// The argument passing the owned value `expr` of type `ty` to an imported
+    /// function, `None` for borrowed tuples and results
+    fn arg(&self, expr: &str, ty: &WitType) -> Option<String> {
+        if !self.by_ref(ty) {
+            return Some(expr.to_string());
+        }
+        match &ty.kind {
+            WitTypeKind::Option(inner) => match &inner.kind {
+                WitTypeKind::String | WitTypeKind::List(_) => Some(format!("{expr}.as_deref()")),
+                _ if self.is_local(inner) => Some(format!("{expr}.as_ref()")),
+                _ => None,
+            },
+            WitTypeKind::String | WitTypeKind::List(_) => Some(format!("&{expr}")),
+            _ if self.is_local(ty) => Some(format!("&{expr}")),
+            _ => None,
+        }
+    }
+
+    /// Whether `ty` is a type of the bindings rather than a type alias or a
+    /// Rust type, so the wrappers can implement traits for it
+    fn is_local(&self, ty: &WitType) -> bool {
+        match &ty.kind {
+            WitTypeKind::Record(_)
+            | WitTypeKind::Variant(_)
+            | WitTypeKind::Enum(_)
+            | WitTypeKind::Flags(_)
+            | WitTypeKind::Resource(_)
+            | WitTypeKind::Iterator(_) => true,
+            WitTypeKind::Named(name) => !matches!(
+                self.definitions.get(name).map(|ty| &ty.kind),
+                Some(WitTypeKind::TypeAlias(_))
+            ),
+            _ => false,
+        }
+    }
+
+    fn is_flags(&self, ty: &WitType) -> bool {
+        match &ty.kind {
+            WitTypeKind::Flags(_) => true,
+            WitTypeKind::Named(name) => matches!(
+                self.definitions.get(name).map(|ty| &ty.kind),
+                Some(WitTypeKind::Flags(_))
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// `header { body }`, unless an impl with the same header was written
+fn impl_block(f: &mut String, headers: &mut BTreeSet<String>, header: String, body: String) {
+    if headers.insert(header.clone()) {
+        writeln!(f, "\n{header} {{\n{}\n}}", indented(&body, "    ")).unwrap();
+    }
+}
+
+/// The owned Rust type `wit-bindgen` uses for a WIT type
+fn guest_type(ty: &WitType) -> String {
+    match &ty.kind {
+        WitTypeKind::U8 => "u8".to_string(),
+        WitTypeKind::U16 => "u16".to_string(),
+        WitTypeKind::U32 => "u32".to_string(),
+        WitTypeKind::U64 => "u64".to_string(),
+        WitTypeKind::S8 => "i8".to_string(),
+        WitTypeKind::S16 => "i16".to_string(),
+        WitTypeKind::S32 => "i32".to_string(),
+        WitTypeKind::S64 => "i64".to_string(),
+        WitTypeKind::Float32 => "f32".to_string(),
+        WitTypeKind::Float64 => "f64".to_string(),
+        WitTypeKind::Char => "char".to_string(),
+        WitTypeKind::Bool => "bool".to_string(),
+        WitTypeKind::String => "String".to_string(),
+        WitTypeKind::Tuple(types) => {
+            let types = types.iter().map(guest_type).collect::<Vec<_>>();
+            match types.as_slice() {
+                [ty] => format!("({ty},)"),
+                _ => format!("({})", types.join(", ")),
+            }
+        }
+        WitTypeKind::List(ty) => format!("Vec<{}>", guest_type(ty)),
+        WitTypeKind::Option(ty) => format!("Option<{}>", guest_type(ty)),
+        WitTypeKind::Result { ok, err } => format!(
+            "Result<{}, {}>",
+            ok.as_deref().map(guest_type).unwrap_or("()".to_string()),
+            err.as_deref().map(guest_type).unwrap_or("()".to_string()),
+        ),
+        WitTypeKind::Borrow(name) => format!("&crate::wit::{}", bindgen_name(name)),
+        _ => format!("crate::wit::{}", bindgen_name(&ty.kind.print_ref())),
+    }
+}
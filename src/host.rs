@@ -0,0 +1,1748 @@
+//! The wasmtime host implementation of the generated WIT
+//!
+//! The generated code refers to the types and traits generated by
+//! `wasmtime::component::bindgen!` through a `wit` module, e.g.
+//! `use my_bindings::maxiquad::macroquad::macroquad as wit;`, and to the
+//! interfaces guests export through a `wit_exports` module, e.g.
+//! `use my_bindings::exports::maxiquad::macroquad as wit_exports;`
+//! As every module is its own interface, `wit` is expected to re-export the
+//! interface modules, whose `Host` traits are implemented one by one, and the
+//! types of all of them, e.g.
+//! `mod wit { pub use my_bindings::maxiquad::macroquad::*; pub use color::*; pub use texture::*; }`
+//!
+//! Conversions between the library's types and the `bindgen!` types are a
+//! separate module, see [`HostCode::print_conversions`].
+//!
+//! Host functions return `wasmtime::Result`, so `bindgen!` needs
+//! `trappable_imports: true`. Values the library can't take, e.g. a list that
+//! doesn't have the length of an array, trap the guest with a
+//! `conversions::ConversionError`.
+//!
+//! Trait objects live in a `table: wasmtime::component::ResourceTable` field
+//! of `HostState`.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+};
+
+use heck::{ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
+
+use crate::{
+    config, query, ArenaField, Conversion, DataCase, Enum, Flags, Interface, Operator, Record,
+    Resource, ResourceMethod, SelfRef, TypeAlias, Variant, WitFunction, WitType, WitTypeKind,
+    BOX_PATH, DISPLAY_PATH, ITERATOR_PATH, OPTION_PATH, RESULT_PATH, STRING_PATH, VEC_PATH,
+};
+
+#[derive(Default)]
+pub struct HostCode {
+    /// Top level items, e.g. trait adapters
+    items: Vec<String>,
+    /// `From` and `TryFrom` impls between the library's types and their
+    /// `bindgen!` types, see [`HostCode::print_conversions`]
+    conversions: Vec<String>,
+    /// Rust paths of the types converting from their `bindgen!` types with
+    /// `TryFrom`
+    try_from: HashSet<String>,
+    /// The recursive structs exported as arenas, see [`HostCode::set_arenas`]
+    arenas: HashSet<rustdoc_types::Id>,
+    /// The interface of the functions being added, see
+    /// [`HostCode::set_interface`]
+    interface: String,
+    /// Methods of the `wit::{interface}::Host` impls, by interface
+    functions: BTreeMap<String, Vec<String>>,
+    /// Functions of the `wit::{interface}::Host{Resource}` impls, by
+    /// interface and resource name
+    resource_functions: BTreeMap<(String, String), Vec<String>>,
+    /// WIT names of the async functions, which `bindgen!` has to be told about
+    async_functions: Vec<String>,
+    /// Rust paths and the WIT names they were given, where those aren't
+    /// just the kebab-case of the Rust name
+    renames: Vec<(String, String)>,
+}
+
+impl HostCode {
+    /// Set the recursive structs exported as arenas, which convert back with
+    /// `TryFrom`, before any conversion refers to them
+    pub fn set_arenas(&mut self, arenas: HashSet<rustdoc_types::Id>) {
+        self.arenas = arenas;
+    }
+
+    /// Add the functions that follow to the `Host` traits of `interface`
+    pub fn set_interface(&mut self, interface: &str) {
+        self.interface = interface.to_string();
+    }
+
+    /// The path of the `bindgen!` trait `trait_` of the current interface,
+    /// e.g. `wit::texture::HostTexture2D`
+    fn host_trait(&self, trait_: &str) -> String {
+        format!("wit::{}::{trait_}", function_name(&self.interface))
+    }
+
+    /// Add the method `f` to the `Host` impl of the current interface
+    fn push_function(&mut self, f: String) {
+        self.functions
+            .entry(self.interface.clone())
+            .or_default()
+            .push(f);
+    }
+
+    /// `impl From<RustError> for wit::Error`, keeping the `Display` text of the
+    /// Rust error in every case that has a payload
+    ///
+    /// `arms` pairs a Rust pattern with the name of the case it maps to.
+    pub fn error_conversion(
+        &mut self,
+        rust_path: &str,
+        variant: &Variant,
+        arms: &[(String, String)],
+    ) {
+        let wit_name = bindgen_name(&variant.name);
+        let mut f = String::new();
+        writeln!(f, "impl From<{rust_path}> for wit::{wit_name} {{").unwrap();
+        writeln!(f, "    fn from(error: {rust_path}) -> Self {{").unwrap();
+        writeln!(f, "        let message = error.to_string();").unwrap();
+        writeln!(f, "        match error {{").unwrap();
+        for (pattern, case) in arms {
+            writeln!(f, "            {pattern} => {},", case_expr(variant, case)).unwrap();
+        }
+        if let Some((fallback, _)) = variant.cases.last() {
+            writeln!(f, "            #[allow(unreachable_patterns)]").unwrap();
+            writeln!(f, "            _ => {},", case_expr(variant, fallback)).unwrap();
+        }
+        writeln!(f, "        }}").unwrap();
+        writeln!(f, "    }}").unwrap();
+        write!(f, "}}").unwrap();
+        self.conversions.push(f);
+    }
+
+    /// A method of the interface's `Host` impl that calls the library
+    /// function at `rust_path`
+    pub fn function(
+        &mut self,
+        rust_path: &str,
+        function: &query::Item<&rustdoc_types::Function>,
+        wit: &WitFunction,
+    ) {
+        let mut f = signature(wit, None);
+        let args = self.args(&mut f, None, function, wit).join(", ");
+        let mut call = format!("{rust_path}({args})");
+        if wit.is_async {
+            call.push_str(".await");
+        }
+        let output = crate::future_output(function).or_else(|| function.output());
+        match (&output, &wit.output) {
+            (Some(output), Some(wit_output))
+                if matches!(wit_output.kind, WitTypeKind::Iterator(_)) =>
+            {
+                let items = iterator_items(&call, output).expect("returns an iterator");
+                let resource = bindgen_name(&wit_output.kind.print_ref());
+                writeln!(
+                    f,
+                    "        let iterator = Box::new({items}) as wit::{resource};"
+                )
+                .unwrap();
+                writeln!(f, "        Ok(self.table.push(iterator)?)").unwrap();
+            }
+            _ => returned(&mut f, &call, output.as_ref(), wit.output.as_ref()),
+        }
+        write!(f, "    }}").unwrap();
+        if wit.is_async {
+            self.async_functions.push(wit.name.clone());
+        }
+        self.push_function(f);
+    }
+
+    /// A method of the interface's `Host` impl returning the value of the
+    /// constant or static `expr` of type `ty`
+    pub fn getter(
+        &mut self,
+        expr: &str,
+        ty: &query::Item<&rustdoc_types::Type>,
+        wit: &WitFunction,
+    ) {
+        self.push_function(getter(expr, ty, wit));
+    }
+
+    /// A `[static]` function of the `resource`'s host impl returning the
+    /// associated constant `expr` of type `ty`
+    pub fn static_getter(
+        &mut self,
+        resource: &str,
+        expr: &str,
+        ty: &query::Item<&rustdoc_types::Type>,
+        wit: &WitFunction,
+    ) {
+        self.resource_functions
+            .entry((self.interface.clone(), resource.to_string()))
+            .or_default()
+            .push(getter(expr, ty, wit));
+    }
+
+    /// A method of the interface's `Host` impl applying the operator `op` to
+    /// values of the library type at `self_path`, see [`crate::operator`]
+    pub fn operator(
+        &mut self,
+        paths: &HashMap<rustdoc_types::Id, Vec<String>>,
+        self_path: &str,
+        op: &str,
+        operator: &Operator,
+    ) {
+        let wit = &operator.wit;
+        let mut f = signature(wit, None);
+        writeln!(
+            f,
+            "        let a: {self_path} = {};",
+            self.to_library("a", self_path)
+        )
+        .unwrap();
+        if let (Some(rhs), Some((name, _))) = (&operator.rhs, wit.params.get(1)) {
+            let rust_type = match rhs.type_kind() {
+                query::TypeKind::Generic("Self") => self_path.to_string(),
+                _ => rust_type(paths, rhs),
+            };
+            writeln!(
+                f,
+                "        let b: {rust_type} = {};",
+                self.rust_arg(name, rhs)
+            )
+            .unwrap();
+        }
+        let expr = match op {
+            "neg" => "-a".to_string(),
+            "not" => "!a".to_string(),
+            "index" => "a[b].clone()".to_string(),
+            "eq" => "a == b".to_string(),
+            "cmp" => "a.partial_cmp(&b).map(|ordering| ordering as i8)".to_string(),
+            _ => {
+                let symbol = match op {
+                    "add" => "+",
+                    "sub" => "-",
+                    "mul" => "*",
+                    "div" => "/",
+                    "rem" => "%",
+                    "bit-and" => "&",
+                    "bit-or" => "|",
+                    "bit-xor" => "^",
+                    "shl" => "<<",
+                    "shr" => ">>",
+                    _ => unreachable!("operator `{op}`"),
+                };
+                format!("(a {symbol} b)")
+            }
+        };
+        match &operator.output {
+            Some(output) => writeln!(f, "        Ok({})", from_rust(&expr, output)).unwrap(),
+            None => writeln!(f, "        Ok({expr})").unwrap(),
+        }
+        write!(f, "    }}").unwrap();
+        self.push_function(f);
+    }
+
+    /// A method of the interface's `Host` impl calling the std trait of
+    /// `helper` for the library type at `rust_path`, see [`crate::helpers`]
+    ///
+    /// Values of resources are borrowed from the resource table, and new ones
+    /// pushed into it, rather than converted.
+    pub fn helper(&mut self, rust_path: &str, helper: config::Helper, wit: &WitFunction) {
+        let mut f = signature(wit, None);
+        let mut is_resource = false;
+        for ty in wit.types() {
+            ty.visit(&mut |ty| {
+                is_resource |= matches!(ty.kind, WitTypeKind::Resource(_) | WitTypeKind::Borrow(_))
+            });
+        }
+        if !wit.params.is_empty() && helper != config::Helper::Parse {
+            if is_resource {
+                writeln!(
+                    f,
+                    "        let value: &{rust_path} = self.table.get(&value)?;"
+                )
+                .unwrap();
+            } else {
+                let value = self.to_library("value", rust_path);
+                writeln!(f, "        let value: {rust_path} = {value};").unwrap();
+            }
+        }
+        let body = match (helper, is_resource) {
+            (config::Helper::ToString, _) => "value.to_string()".to_string(),
+            (config::Helper::DebugString, _) => "format!(\"{value:?}\")".to_string(),
+            (config::Helper::Default, false) => format!("{rust_path}::default().into()"),
+            (config::Helper::Default, true) => {
+                format!("self.table.push({rust_path}::default())?")
+            }
+            (config::Helper::Clone, false) => "value.clone().into()".to_string(),
+            (config::Helper::Clone, true) => {
+                writeln!(f, "        let value = value.clone();").unwrap();
+                "self.table.push(value)?".to_string()
+            }
+            (config::Helper::Parse, false) => format!(
+                "s.parse::<{rust_path}>().map(Into::into).map_err(|error| error.to_string())"
+            ),
+            (config::Helper::Parse, true) => format!(
+                "match s.parse::<{rust_path}>() {{ Ok(value) => Ok(self.table.push(value)?), Err(error) => Err(error.to_string()) }}"
+            ),
+        };
+        writeln!(f, "        Ok({body})").unwrap();
+        write!(f, "    }}").unwrap();
+        self.push_function(f);
+    }
+
+    /// `From` impls between the `bitflags!`-style struct at `rust_path` and
+    /// its WIT `flags`, flag by flag as the bits might differ
+    ///
+    /// `consts` are the associated constants of the struct, in the order of
+    /// the WIT flags.
+    pub fn flags_conversion(&mut self, rust_path: &str, flags: &Flags, consts: &[String]) {
+        let wit_name = bindgen_name(&flags.name);
+        let mut f = String::new();
+        writeln!(f, "impl From<{rust_path}> for wit::{wit_name} {{").unwrap();
+        writeln!(f, "    fn from(flags: {rust_path}) -> Self {{").unwrap();
+        writeln!(f, "        let mut wit = Self::empty();").unwrap();
+        for (flag, constant) in flags.flags.iter().zip(consts) {
+            writeln!(
+                f,
+                "        if flags.contains({rust_path}::{constant}) {{ wit |= Self::{}; }}",
+                flag.to_shouty_snake_case()
+            )
+            .unwrap();
+        }
+        writeln!(f, "        wit").unwrap();
+        writeln!(f, "    }}").unwrap();
+        writeln!(f, "}}\n").unwrap();
+        writeln!(f, "impl From<wit::{wit_name}> for {rust_path} {{").unwrap();
+        writeln!(f, "    fn from(flags: wit::{wit_name}) -> Self {{").unwrap();
+        writeln!(f, "        let mut rust = Self::empty();").unwrap();
+        for (flag, constant) in flags.flags.iter().zip(consts) {
+            writeln!(
+                f,
+                "        if flags.contains(wit::{wit_name}::{}) {{ rust |= Self::{constant}; }}",
+                flag.to_shouty_snake_case()
+            )
+            .unwrap();
+        }
+        writeln!(f, "        rust").unwrap();
+        writeln!(f, "    }}").unwrap();
+        write!(f, "}}").unwrap();
+        self.conversions.push(f);
+    }
+
+    /// `From` impls between the record struct at `rust_path` and its WIT
+    /// `record`, field by field
+    ///
+    /// `#[non_exhaustive]` structs can't be built with a struct literal, so they
+    /// only convert to WIT.
+    pub fn record_conversion(
+        &mut self,
+        rust_path: &str,
+        record: &Record,
+        fields: &[(&str, query::Item<&rustdoc_types::Type>)],
+        non_exhaustive: bool,
+    ) {
+        let wit_name = bindgen_name(&record.name);
+        let mut f = String::new();
+        writeln!(f, "impl From<{rust_path}> for wit::{wit_name} {{").unwrap();
+        writeln!(f, "    fn from(value: {rust_path}) -> Self {{").unwrap();
+        writeln!(f, "        Self {{").unwrap();
+        for ((name, ty), (wit_field, _)) in fields.iter().zip(&record.fields) {
+            let field = from_rust(&format!("value.{name}"), ty);
+            writeln!(f, "            {}: {field},", function_name(wit_field)).unwrap();
+        }
+        writeln!(f, "        }}").unwrap();
+        writeln!(f, "    }}").unwrap();
+        writeln!(f, "}}\n").unwrap();
+        if non_exhaustive {
+            write!(
+                f,
+                "// no `From<wit::{wit_name}>`, `{rust_path}` is `#[non_exhaustive]`"
+            )
+            .unwrap();
+            self.conversions.push(f);
+            return;
+        }
+        if !fields.iter().any(|(_, ty)| self.is_fallible(ty)) {
+            writeln!(f, "impl From<wit::{wit_name}> for {rust_path} {{").unwrap();
+            writeln!(f, "    fn from(value: wit::{wit_name}) -> Self {{").unwrap();
+            writeln!(f, "        Self {{").unwrap();
+            for ((name, ty), (wit_field, _)) in fields.iter().zip(&record.fields) {
+                let field = self.rust_arg(&format!("value.{}", function_name(wit_field)), ty);
+                writeln!(f, "            {name}: {field},").unwrap();
+            }
+            writeln!(f, "        }}").unwrap();
+            writeln!(f, "    }}").unwrap();
+            write!(f, "}}").unwrap();
+            self.conversions.push(f);
+            return;
+        }
+        self.try_from.insert(rust_path.to_string());
+        writeln!(f, "impl TryFrom<wit::{wit_name}> for {rust_path} {{").unwrap();
+        writeln!(f, "    type Error = ConversionError;\n").unwrap();
+        writeln!(
+            f,
+            "    fn try_from(value: wit::{wit_name}) -> Result<Self, Self::Error> {{"
+        )
+        .unwrap();
+        writeln!(f, "        Ok(Self {{").unwrap();
+        for ((name, ty), (wit_field, _)) in fields.iter().zip(&record.fields) {
+            let value = format!("value.{}", function_name(wit_field));
+            let field = self.rust_value(&value, ty, Failure::Propagate);
+            writeln!(f, "            {name}: {field},").unwrap();
+        }
+        writeln!(f, "        }})").unwrap();
+        writeln!(f, "    }}").unwrap();
+        write!(f, "}}").unwrap();
+        self.conversions.push(f);
+    }
+
+    /// `From` and `TryFrom` impls between the struct at `rust_path`, which
+    /// contains itself, and the arena `record` of `entry` records it is
+    /// exported as, see [`crate::arena`]
+    ///
+    /// Entries are pushed after the entries they refer to, so converting back
+    /// builds every value from values already built. An index that isn't one
+    /// of an earlier entry, or that was already taken, fails the conversion.
+    pub fn arena_conversion(
+        &mut self,
+        rust_path: &str,
+        record: &Record,
+        entry: &Record,
+        fields: &[ArenaField],
+        non_exhaustive: bool,
+    ) {
+        let wit_name = bindgen_name(&record.name);
+        let entry_name = bindgen_name(&entry.name);
+        let mut f = String::new();
+        writeln!(f, "impl From<{rust_path}> for wit::{wit_name} {{").unwrap();
+        writeln!(f, "    fn from(value: {rust_path}) -> Self {{").unwrap();
+        writeln!(
+            f,
+            "        fn push(value: {rust_path}, entries: &mut Vec<wit::{entry_name}>) -> u32 {{"
+        )
+        .unwrap();
+        writeln!(f, "            let entry = wit::{entry_name} {{").unwrap();
+        for ((name, ty, self_ref), (wit_field, _)) in fields.iter().zip(&entry.fields) {
+            let value = format!("value.{name}");
+            let field = match self_ref {
+                None => from_rust(&value, ty),
+                Some(SelfRef::Box) => format!("push(*{value}, entries)"),
+                Some(SelfRef::OptionBox) => format!("{value}.map(|child| push(*child, entries))"),
+                Some(SelfRef::Vec { boxed }) => format!(
+                    "{value}.into_iter().map(|child| push({}child, entries)).collect()",
+                    if *boxed { "*" } else { "" }
+                ),
+            };
+            writeln!(f, "                {}: {field},", function_name(wit_field)).unwrap();
+        }
+        writeln!(f, "            }};").unwrap();
+        writeln!(f, "            entries.push(entry);").unwrap();
+        writeln!(f, "            (entries.len() - 1) as u32").unwrap();
+        writeln!(f, "        }}\n").unwrap();
+        writeln!(f, "        let mut entries = Vec::new();").unwrap();
+        writeln!(f, "        push(value, &mut entries);").unwrap();
+        writeln!(f, "        Self {{ entries }}").unwrap();
+        writeln!(f, "    }}").unwrap();
+        writeln!(f, "}}\n").unwrap();
+        if non_exhaustive {
+            write!(
+                f,
+                "// no `From<wit::{wit_name}>`, `{rust_path}` is `#[non_exhaustive]`"
+            )
+            .unwrap();
+            self.conversions.push(f);
+            return;
+        }
+        self.try_from.insert(rust_path.to_string());
+        writeln!(f, "impl TryFrom<wit::{wit_name}> for {rust_path} {{").unwrap();
+        writeln!(f, "    type Error = ConversionError;\n").unwrap();
+        writeln!(
+            f,
+            "    fn try_from(value: wit::{wit_name}) -> Result<Self, Self::Error> {{"
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "        let mut values: Vec<Option<{rust_path}>> = Vec::new();"
+        )
+        .unwrap();
+        writeln!(f, "        for entry in value.entries {{").unwrap();
+        writeln!(f, "            let mut take = |index: u32| {{").unwrap();
+        writeln!(
+            f,
+            "                values.get_mut(index as usize).and_then(Option::take).ok_or(ConversionError::Index {{ type_: {rust_path:?}, index }})"
+        )
+        .unwrap();
+        writeln!(f, "            }};").unwrap();
+        writeln!(f, "            let value = {rust_path} {{").unwrap();
+        for ((name, ty, self_ref), (wit_field, _)) in fields.iter().zip(&entry.fields) {
+            let value = format!("entry.{}", function_name(wit_field));
+            let field = match self_ref {
+                None => self.rust_value(&value, ty, Failure::Propagate),
+                Some(SelfRef::Box) => format!("Box::new(take({value})?)"),
+                Some(SelfRef::OptionBox) => {
+                    format!("{value}.map(|index| take(index).map(Box::new)).transpose()?")
+                }
+                Some(SelfRef::Vec { boxed: false }) => {
+                    format!("{value}.into_iter().map(&mut take).collect::<Result<_, _>>()?")
+                }
+                Some(SelfRef::Vec { boxed: true }) => format!(
+                    "{value}.into_iter().map(|index| take(index).map(Box::new)).collect::<Result<_, _>>()?"
+                ),
+            };
+            writeln!(f, "                {name}: {field},").unwrap();
+        }
+        writeln!(f, "            }};").unwrap();
+        writeln!(f, "            values.push(Some(value));").unwrap();
+        writeln!(f, "        }}").unwrap();
+        writeln!(
+            f,
+            "        values.pop().flatten().ok_or(ConversionError::Empty {{ type_: {rust_path:?} }})"
+        )
+        .unwrap();
+        writeln!(f, "    }}").unwrap();
+        write!(f, "}}").unwrap();
+        self.conversions.push(f);
+    }
+
+    /// `From` impls between the C-like enum at `rust_path` and its WIT `enum`,
+    /// case by case so the Rust discriminants are kept
+    ///
+    /// `arms` pairs a Rust pattern with the name of the case it maps to, `_`
+    /// for the case of variants of `#[non_exhaustive]` enums added later,
+    /// which makes converting back a `TryFrom`.
+    pub fn enum_conversion(&mut self, rust_path: &str, enum_: &Enum, arms: &[(String, String)]) {
+        let wit_name = bindgen_name(&enum_.name);
+        let mut f = String::new();
+        writeln!(f, "impl From<{rust_path}> for wit::{wit_name} {{").unwrap();
+        writeln!(f, "    fn from(value: {rust_path}) -> Self {{").unwrap();
+        writeln!(f, "        match value {{").unwrap();
+        for (pattern, case) in arms {
+            if pattern == "_" {
+                writeln!(f, "            #[allow(unreachable_patterns)]").unwrap();
+            }
+            writeln!(f, "            {pattern} => Self::{},", bindgen_name(case)).unwrap();
+        }
+        writeln!(f, "        }}").unwrap();
+        writeln!(f, "    }}").unwrap();
+        writeln!(f, "}}\n").unwrap();
+        if !arms.iter().any(|(pattern, _)| pattern == "_") {
+            writeln!(f, "impl From<wit::{wit_name}> for {rust_path} {{").unwrap();
+            writeln!(f, "    fn from(value: wit::{wit_name}) -> Self {{").unwrap();
+            writeln!(f, "        match value {{").unwrap();
+            for (pattern, case) in arms {
+                writeln!(
+                    f,
+                    "            wit::{wit_name}::{} => {pattern},",
+                    bindgen_name(case)
+                )
+                .unwrap();
+            }
+            writeln!(f, "        }}").unwrap();
+            writeln!(f, "    }}").unwrap();
+            write!(f, "}}").unwrap();
+            self.conversions.push(f);
+            return;
+        }
+        self.try_from.insert(rust_path.to_string());
+        writeln!(f, "impl TryFrom<wit::{wit_name}> for {rust_path} {{").unwrap();
+        writeln!(f, "    type Error = ConversionError;\n").unwrap();
+        writeln!(
+            f,
+            "    fn try_from(value: wit::{wit_name}) -> Result<Self, Self::Error> {{"
+        )
+        .unwrap();
+        writeln!(f, "        match value {{").unwrap();
+        for (pattern, case) in arms {
+            let case = bindgen_name(case);
+            if pattern == "_" {
+                writeln!(
+                    f,
+                    "            wit::{wit_name}::{case} => Err(ConversionError::Unknown {{ type_: {rust_path:?} }}),"
+                )
+                .unwrap();
+            } else {
+                writeln!(f, "            wit::{wit_name}::{case} => Ok({pattern}),").unwrap();
+            }
+        }
+        writeln!(f, "        }}").unwrap();
+        writeln!(f, "    }}").unwrap();
+        write!(f, "}}").unwrap();
+        self.conversions.push(f);
+    }
+
+    /// `From` impls between the data enum at `rust_path` and its WIT
+    /// `variant`, with the fields of each variant as the payload of its case,
+    /// see [`crate::data_variant`]
+    ///
+    /// Converting back is a `TryFrom` when a payload is fallible, or when the
+    /// variant has an extra case for variants of a `#[non_exhaustive]` enum.
+    pub fn variant_conversion(&mut self, rust_path: &str, variant: &Variant, cases: &[DataCase]) {
+        let wit_name = bindgen_name(&variant.name);
+        // the payload of a case, and the Rust pattern or constructor of its variant
+        let payload =
+            |case: &DataCase,
+             convert: &dyn Fn(&str, &query::Item<&rustdoc_types::Type>) -> String| {
+                let values = case
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, (_, ty))| convert(&format!("t{idx}"), ty))
+                    .collect::<Vec<_>>();
+                let payload = match values.as_slice() {
+                    [] => String::new(),
+                    [value] => format!("({value})"),
+                    values => format!("(({}))", values.join(", ")),
+                };
+                let fields = match case.fields.first() {
+                    None => String::new(),
+                    Some((None, _)) => format!("({})", values.join(", ")),
+                    Some((Some(_), _)) => {
+                        let fields = case
+                            .fields
+                            .iter()
+                            .zip(&values)
+                            .map(|((name, _), value)| {
+                                format!("{}: {value}", name.unwrap_or_default())
+                            })
+                            .collect::<Vec<_>>();
+                        format!(" {{ {} }}", fields.join(", "))
+                    }
+                };
+                (payload, fields)
+            };
+        let mut f = String::new();
+        writeln!(f, "impl From<{rust_path}> for wit::{wit_name} {{").unwrap();
+        writeln!(f, "    fn from(value: {rust_path}) -> Self {{").unwrap();
+        writeln!(f, "        match value {{").unwrap();
+        for case in cases {
+            let (_, pattern) = payload(case, &|var, _| var.to_string());
+            let (payload, _) = payload(case, &|var, ty| from_rust(var, ty));
+            writeln!(
+                f,
+                "            {rust_path}::{}{pattern} => Self::{}{payload},",
+                case.variant,
+                bindgen_name(&case.case)
+            )
+            .unwrap();
+        }
+        let unknown = variant
+            .cases
+            .get(cases.len())
+            .map(|(case, _)| bindgen_name(case));
+        if let Some(unknown) = &unknown {
+            writeln!(f, "            #[allow(unreachable_patterns)]").unwrap();
+            writeln!(f, "            _ => Self::{unknown},").unwrap();
+        }
+        writeln!(f, "        }}").unwrap();
+        writeln!(f, "    }}").unwrap();
+        writeln!(f, "}}\n").unwrap();
+
+        let fallible = unknown.is_some()
+            || cases
+                .iter()
+                .any(|case| case.fields.iter().any(|(_, ty)| self.is_fallible(ty)));
+        let (ok, end) = if fallible {
+            self.try_from.insert(rust_path.to_string());
+            writeln!(f, "impl TryFrom<wit::{wit_name}> for {rust_path} {{").unwrap();
+            writeln!(f, "    type Error = ConversionError;\n").unwrap();
+            writeln!(
+                f,
+                "    fn try_from(value: wit::{wit_name}) -> Result<Self, Self::Error> {{"
+            )
+            .unwrap();
+            ("Ok(", ")")
+        } else {
+            writeln!(f, "impl From<wit::{wit_name}> for {rust_path} {{").unwrap();
+            writeln!(f, "    fn from(value: wit::{wit_name}) -> Self {{").unwrap();
+            ("", "")
+        };
+        writeln!(f, "        match value {{").unwrap();
+        for case in cases {
+            let (pattern, _) = payload(case, &|var, _| var.to_string());
+            let (_, fields) = payload(case, &|var, ty| {
+                self.rust_value(var, ty, Failure::Propagate)
+            });
+            writeln!(
+                f,
+                "            wit::{wit_name}::{}{pattern} => {ok}{rust_path}::{}{fields}{end},",
+                bindgen_name(&case.case),
+                case.variant
+            )
+            .unwrap();
+        }
+        if let Some(unknown) = &unknown {
+            writeln!(
+                f,
+                "            wit::{wit_name}::{unknown} => Err(ConversionError::Unknown {{ type_: {rust_path:?} }}),"
+            )
+            .unwrap();
+        }
+        writeln!(f, "        }}").unwrap();
+        writeln!(f, "    }}").unwrap();
+        write!(f, "}}").unwrap();
+        self.conversions.push(f);
+    }
+
+    /// Methods of the interface's `Host` impl casting the C-like enum at
+    /// `rust_path` to its discriminant and back, see
+    /// [`crate::discriminant_functions`]
+    pub fn discriminant_functions(
+        &mut self,
+        rust_path: &str,
+        to: &WitFunction,
+        from: &WitFunction,
+        arms: &[(String, String)],
+    ) {
+        let int = to
+            .output
+            .as_ref()
+            .map(host_type)
+            .expect("returns the discriminant");
+        let mut f = signature(to, None);
+        let value = self.to_library("value", rust_path);
+        writeln!(f, "        let value: {rust_path} = {value};").unwrap();
+        writeln!(f, "        Ok(value as {int})").unwrap();
+        write!(f, "    }}").unwrap();
+        self.push_function(f);
+
+        let mut f = signature(from, None);
+        writeln!(f, "        Ok(match discriminant {{").unwrap();
+        for (pattern, _) in arms.iter().filter(|(pattern, _)| pattern != "_") {
+            writeln!(
+                f,
+                "            discriminant if discriminant == {pattern} as {int} => Some({pattern}.into()),"
+            )
+            .unwrap();
+        }
+        writeln!(f, "            _ => None,").unwrap();
+        writeln!(f, "        }})").unwrap();
+        write!(f, "    }}").unwrap();
+        self.push_function(f);
+    }
+
+    /// A method of the interface's `Host` impl converting with a `From`,
+    /// `Into`, `TryFrom` or `TryInto` impl, see [`crate::conversion`]
+    pub fn conversion(
+        &mut self,
+        paths: &HashMap<rustdoc_types::Id, Vec<String>>,
+        conversion: &Conversion,
+    ) {
+        let mut f = signature(&conversion.wit, None);
+        writeln!(
+            f,
+            "        let value: {} = {};",
+            rust_type(paths, &conversion.from),
+            self.rust_arg("value", &conversion.from)
+        )
+        .unwrap();
+        let to = rust_type(paths, &conversion.to);
+        match &conversion.error {
+            Some(error) => {
+                writeln!(
+                    f,
+                    "        let converted: Result<{to}, _> = value.try_into();"
+                )
+                .unwrap();
+                writeln!(
+                    f,
+                    "        Ok(converted.map(|value| {}).map_err(|error| {}))",
+                    from_rust("value", &conversion.to),
+                    from_rust("error", error)
+                )
+                .unwrap();
+            }
+            None => {
+                writeln!(f, "        let converted: {to} = value.into();").unwrap();
+                writeln!(f, "        Ok({})", from_rust("converted", &conversion.to)).unwrap();
+            }
+        }
+        write!(f, "    }}").unwrap();
+        self.push_function(f);
+    }
+
+    /// A struct implementing the Rust trait at `rust_path` by calling the
+    /// guest's export of `interface`
+    pub fn trait_adapter(
+        &mut self,
+        paths: &HashMap<rustdoc_types::Id, Vec<String>>,
+        rust_path: &str,
+        interface: &Interface,
+        methods: &[(query::Item<&rustdoc_types::Function>, WitFunction)],
+    ) {
+        let adapter = format!("{}Adapter", bindgen_name(&interface.name));
+        let module = function_name(&interface.name);
+        let mut f = String::new();
+        writeln!(
+            f,
+            "/// Implements `{rust_path}` by calling the guest's `{}` export",
+            interface.name
+        )
+        .unwrap();
+        writeln!(f, "pub struct {adapter}<T: 'static> {{").unwrap();
+        writeln!(f, "    pub store: std::cell::RefCell<wasmtime::Store<T>>,").unwrap();
+        writeln!(f, "    pub guest: wit_exports::{module}::Guest,").unwrap();
+        writeln!(f, "}}\n").unwrap();
+        writeln!(f, "impl<T: 'static> {rust_path} for {adapter}<T> {{").unwrap();
+        for (idx, (method, wit)) in methods.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f).unwrap();
+            }
+            let receiver = match method.receiver().map(|ty| ty.type_kind()) {
+                Some(query::TypeKind::BorrowedRef(borrowed)) if borrowed.is_mutable() => {
+                    "&mut self"
+                }
+                Some(query::TypeKind::BorrowedRef(_)) => "&self",
+                _ => "self",
+            };
+            write!(f, "    fn {}({receiver}", method.name()).unwrap();
+            for ((_, ty), (name, _)) in method.params().zip(&wit.params) {
+                write!(f, ", {}: {}", function_name(name), rust_type(paths, &ty)).unwrap();
+            }
+            write!(f, ")").unwrap();
+            if let Some(output) = method.output() {
+                write!(f, " -> {}", rust_type(paths, &output)).unwrap();
+            }
+            writeln!(f, " {{").unwrap();
+
+            let mut args = vec!["&mut *self.store.borrow_mut()".to_string()];
+            for ((_, ty), (name, wit_ty)) in method.params().zip(&wit.params) {
+                let arg = from_rust(&function_name(name), &ty);
+                match wit_ty.kind {
+                    WitTypeKind::String | WitTypeKind::List(_) => args.push(format!("&{arg}")),
+                    _ => args.push(arg),
+                }
+            }
+            let call = format!(
+                "self.guest.call_{}({})",
+                function_name(&wit.name),
+                args.join(", ")
+            );
+            match method.output() {
+                Some(output) => {
+                    writeln!(
+                        f,
+                        "        let output = {call}.expect(\"guest call failed\");"
+                    )
+                    .unwrap();
+                    writeln!(
+                        f,
+                        "        {}",
+                        self.rust_value("output", &output, Failure::Panic)
+                    )
+                    .unwrap();
+                }
+                None => writeln!(f, "        {call}.expect(\"guest call failed\")").unwrap(),
+            }
+            writeln!(f, "    }}").unwrap();
+        }
+        write!(f, "}}").unwrap();
+        self.items.push(f);
+    }
+
+    /// The `Box<dyn Iterator + Send>` backing an iterator resource, and its
+    /// `next` method
+    pub fn iterator(&mut self, resource: &Resource, item: &WitType) {
+        let name = bindgen_name(&resource.name);
+        let handle = format!("wasmtime::component::Resource<wit::{name}>");
+        let mut f = String::new();
+        writeln!(
+            f,
+            "/// Backs the `{}` resource, through `with` in `bindgen!`",
+            resource.name
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "pub type {name} = Box<dyn Iterator<Item = {}> + Send>;\n",
+            host_type(item)
+        )
+        .unwrap();
+        let host_trait = self.host_trait(&format!("Host{name}"));
+        writeln!(f, "impl {host_trait} for HostState {{").unwrap();
+        writeln!(
+            f,
+            "    fn next(&mut self, self_: {handle}) -> wasmtime::Result<Option<{}>> {{",
+            host_type(item)
+        )
+        .unwrap();
+        writeln!(f, "        let iterator = self.table.get_mut(&self_)?;").unwrap();
+        writeln!(f, "        Ok(iterator.next())").unwrap();
+        writeln!(f, "    }}\n").unwrap();
+        writeln!(
+            f,
+            "    fn drop(&mut self, rep: {handle}) -> wasmtime::Result<()> {{"
+        )
+        .unwrap();
+        writeln!(f, "        self.table.delete(rep)?;").unwrap();
+        writeln!(f, "        Ok(())").unwrap();
+        writeln!(f, "    }}").unwrap();
+        write!(f, "}}").unwrap();
+        self.items.push(f);
+    }
+
+    /// The `Box<dyn Trait>` backing a trait object resource, and the host
+    /// methods of the resource calling the trait's methods at `rust_path`
+    pub fn trait_object(
+        &mut self,
+        rust_path: &str,
+        resource: &Resource,
+        methods: &[(query::Item<&rustdoc_types::Function>, WitFunction)],
+    ) {
+        let name = bindgen_name(&resource.name);
+        let handle = format!("wasmtime::component::Resource<wit::{name}>");
+        let mut f = String::new();
+        writeln!(
+            f,
+            "/// Backs the `{}` resource, through `with` in `bindgen!`",
+            resource.name
+        )
+        .unwrap();
+        writeln!(f, "pub type {name} = Box<dyn {rust_path} + Send>;\n").unwrap();
+        let host_trait = self.host_trait(&format!("Host{name}"));
+        writeln!(f, "impl {host_trait} for HostState {{").unwrap();
+        for (method, wit) in methods {
+            f.push_str(&signature(wit, Some(&handle)));
+            let args = self
+                .args(&mut f, method.receiver().as_ref(), method, wit)
+                .join(", ");
+            let call = format!("object.{}({args})", method.name());
+            returned(&mut f, &call, method.output().as_ref(), wit.output.as_ref());
+            writeln!(f, "    }}\n").unwrap();
+        }
+        writeln!(
+            f,
+            "    fn drop(&mut self, rep: {handle}) -> wasmtime::Result<()> {{"
+        )
+        .unwrap();
+        writeln!(f, "        self.table.delete(rep)?;").unwrap();
+        writeln!(f, "        Ok(())").unwrap();
+        writeln!(f, "    }}").unwrap();
+        write!(f, "}}").unwrap();
+        self.items.push(f);
+    }
+
+    /// The library type at `rust_path` backing `resource`, and the host
+    /// functions of the resource calling its `methods`
+    pub fn resource_methods(
+        &mut self,
+        rust_path: &str,
+        resource: &str,
+        methods: &[ResourceMethod],
+    ) {
+        let name = bindgen_name(resource);
+        let handle = format!("wasmtime::component::Resource<wit::{name}>");
+        self.items.push(format!(
+            "/// Backs the `{resource}` resource, through `with` in `bindgen!`\npub type {name} = {rust_path};"
+        ));
+        let mut functions = Vec::new();
+        for method in methods {
+            let wit = &method.wit;
+            let handle = method.function.receiver().map(|_| handle.as_str());
+            let mut f = signature(wit, handle);
+            let receiver = method.function.receiver();
+            let mut args = self.args(&mut f, receiver.as_ref(), &method.function, wit);
+            if receiver.is_some() {
+                args.insert(0, "object".to_string());
+            }
+            // trait methods are called through the trait, as methods of other
+            // traits may have the same name
+            let function = match &method.trait_ {
+                Some(trait_) => format!("<{rust_path} as {trait_}>::{}", method.function.name()),
+                None => format!("{rust_path}::{}", method.function.name()),
+            };
+            let call = format!("{function}({})", args.join(", "));
+            returned(
+                &mut f,
+                &call,
+                method.function.output().as_ref(),
+                wit.output.as_ref(),
+            );
+            write!(f, "    }}").unwrap();
+            functions.push(f);
+        }
+        self.resource_functions
+            .entry((self.interface.clone(), resource.to_string()))
+            .or_default()
+            .extend(functions);
+    }
+
+    /// Record that the item at `rust_path` is called `wit_name` in WIT
+    pub fn rename(&mut self, rust_path: &str, wit_name: &str) {
+        self.renames
+            .push((rust_path.to_string(), wit_name.to_string()));
+    }
+
+    /// Convert the `bindgen!` value `var` to the library type at `rust_path`
+    /// in a host function, trapping if it converts with `TryFrom`
+    fn to_library(&self, var: &str, rust_path: &str) -> String {
+        if self.try_from.contains(rust_path) {
+            format!("{var}.try_into()?")
+        } else {
+            format!("{var}.into()")
+        }
+    }
+
+    /// The conversion impls, as a module to put next to the `wit` module
+    pub fn print_conversions(&self) -> impl std::fmt::Display {
+        let mut f = String::new();
+        writeln!(
+            f,
+            "//! `From` and `TryFrom` impls between the library's types and their `bindgen!` types\n"
+        )
+        .unwrap();
+        writeln!(f, "use super::wit;\n").unwrap();
+        writeln!(f, "/// A `bindgen!` value the library's type can't hold").unwrap();
+        writeln!(f, "#[derive(Debug)]").unwrap();
+        writeln!(f, "pub enum ConversionError {{").unwrap();
+        writeln!(
+            f,
+            "    /// A list that doesn't have the length of the Rust array it stands for"
+        )
+        .unwrap();
+        writeln!(f, "    Length {{ expected: usize, found: usize }},").unwrap();
+        writeln!(
+            f,
+            "    /// The case for variants of a `#[non_exhaustive]` enum added in later versions"
+        )
+        .unwrap();
+        writeln!(f, "    Unknown {{ type_: &'static str }},").unwrap();
+        writeln!(
+            f,
+            "    /// An index of an arena entry that isn't an earlier entry not referred to yet"
+        )
+        .unwrap();
+        writeln!(f, "    Index {{ type_: &'static str, index: u32 }},").unwrap();
+        writeln!(f, "    /// An arena without entries, so without a root").unwrap();
+        writeln!(f, "    Empty {{ type_: &'static str }},").unwrap();
+        writeln!(f, "}}\n").unwrap();
+        writeln!(f, "impl std::fmt::Display for ConversionError {{").unwrap();
+        writeln!(
+            f,
+            "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{"
+        )
+        .unwrap();
+        writeln!(f, "        match self {{").unwrap();
+        writeln!(
+            f,
+            "            Self::Length {{ expected, found }} => write!(f, \"expected {{expected}} items, found {{found}}\"),"
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "            Self::Unknown {{ type_ }} => write!(f, \"not a variant of `{{type_}}` in this version\"),"
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "            Self::Index {{ type_, index }} => write!(f, \"entry {{index}} of `{{type_}}` isn't an earlier entry not referred to yet\"),"
+        )
+        .unwrap();
+        writeln!(
+            f,
+            "            Self::Empty {{ type_ }} => write!(f, \"no entries for `{{type_}}`\"),"
+        )
+        .unwrap();
+        writeln!(f, "        }}").unwrap();
+        writeln!(f, "    }}").unwrap();
+        writeln!(f, "}}\n").unwrap();
+        writeln!(f, "impl std::error::Error for ConversionError {{}}").unwrap();
+        for conversion in &self.conversions {
+            writeln!(f, "\n{conversion}").unwrap();
+        }
+        f
+    }
+
+    pub fn print(&self) -> impl std::fmt::Display {
+        let mut f = String::new();
+        if !self.renames.is_empty() {
+            writeln!(
+                f,
+                "/// Rust paths and their WIT names, where those differ from the kebab-case"
+            )
+            .unwrap();
+            writeln!(f, "pub const WIT_NAMES: &[(&str, &str)] = &[").unwrap();
+            for (rust_path, wit_name) in &self.renames {
+                writeln!(f, "    ({rust_path:?}, {wit_name:?}),").unwrap();
+            }
+            writeln!(f, "];\n").unwrap();
+        }
+        for item in &self.items {
+            writeln!(f, "{item}\n").unwrap();
+        }
+        for ((interface, resource), functions) in &self.resource_functions {
+            let name = bindgen_name(resource);
+            let module = function_name(interface);
+            writeln!(f, "impl wit::{module}::Host{name} for HostState {{").unwrap();
+            for function in functions {
+                writeln!(f, "{function}\n").unwrap();
+            }
+            writeln!(
+                f,
+                "    fn drop(&mut self, rep: wasmtime::component::Resource<wit::{name}>) -> wasmtime::Result<()> {{"
+            )
+            .unwrap();
+            writeln!(f, "        self.table.delete(rep)?;").unwrap();
+            writeln!(f, "        Ok(())").unwrap();
+            writeln!(f, "    }}").unwrap();
+            writeln!(f, "}}\n").unwrap();
+        }
+        if !self.async_functions.is_empty() {
+            let names = self
+                .async_functions
+                .iter()
+                .map(|name| format!("{name:?}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            writeln!(
+                f,
+                "// needs `async: {{ only_imports: [{names}] }}` in `bindgen!`"
+            )
+            .unwrap();
+        }
+        for (interface, functions) in &self.functions {
+            let module = function_name(interface);
+            writeln!(f, "impl wit::{module}::Host for HostState {{").unwrap();
+            for (idx, function) in functions.iter().enumerate() {
+                if idx > 0 {
+                    writeln!(f).unwrap();
+                }
+                writeln!(f, "{function}").unwrap();
+            }
+            writeln!(f, "}}\n").unwrap();
+        }
+        f
+    }
+}
+
+/// `fn name(&mut self) -> wasmtime::Result<T>` returning the constant or
+/// static `expr` of type `ty`
+fn getter(expr: &str, ty: &query::Item<&rustdoc_types::Type>, wit: &WitFunction) -> String {
+    let mut f = signature(wit, None);
+    returned(&mut f, expr, Some(ty), wit.output.as_ref());
+    write!(f, "    }}").unwrap();
+    f
+}
+
+/// The `ResourceTable` method borrowing a handle passed as `ty`, or within
+/// `ty` as in `Option<&mut T>`
+fn table_get(ty: &query::Item<&rustdoc_types::Type>) -> &'static str {
+    let is_mutable = |ty: &query::Item<&rustdoc_types::Type>| matches!(ty.type_kind(), query::TypeKind::BorrowedRef(borrowed) if borrowed.is_mutable());
+    let mutable = match ty.type_kind() {
+        query::TypeKind::BorrowedRef(_) => is_mutable(ty),
+        _ => ty.type_args().next().is_some_and(|arg| is_mutable(&arg)),
+    };
+    if mutable {
+        "get_mut"
+    } else {
+        "get"
+    }
+}
+
+/// The end of a host function returning `expr`, the library value of type
+/// `ty`, as `wit`
+///
+/// Resources are pushed into the resource table, also within an `Option` or
+/// the `Ok` of a `Result`, see [`crate::handle_position`].
+fn returned(
+    f: &mut String,
+    expr: &str,
+    ty: Option<&query::Item<&rustdoc_types::Type>>,
+    wit: Option<&WitType>,
+) {
+    let (Some(ty), Some(wit)) = (ty, wit) else {
+        writeln!(f, "        {expr};\n        Ok(())").unwrap();
+        return;
+    };
+    let is_handle = |ty: &WitType| matches!(ty.kind, WitTypeKind::Resource(_));
+    let value = match &wit.kind {
+        _ if is_handle(wit) => "self.table.push(value)?".to_string(),
+        WitTypeKind::Option(inner) if is_handle(inner) => {
+            "match value { Some(value) => Some(self.table.push(value)?), None => None }".to_string()
+        }
+        WitTypeKind::Result { ok: Some(ok), .. } if is_handle(ok) => {
+            let err = ty
+                .type_args()
+                .nth(1)
+                .map_or("error".to_string(), |err| from_rust("error", &err));
+            format!(
+                "match value {{ Ok(value) => Ok(self.table.push(value)?), Err(error) => Err({err}) }}"
+            )
+        }
+        _ => {
+            writeln!(f, "        Ok({})", from_rust(expr, ty)).unwrap();
+            return;
+        }
+    };
+    writeln!(f, "        let value = {expr};").unwrap();
+    writeln!(f, "        Ok({value})").unwrap();
+}
+
+/// The first line of the host function of `wit`, e.g.
+/// `    fn draw_circle(&mut self, x: f32) -> wasmtime::Result<()> {`, taking
+/// the resource `handle` of methods first
+fn signature(wit: &WitFunction, handle: Option<&str>) -> String {
+    let mut f = String::new();
+    let async_ = if wit.is_async { "async " } else { "" };
+    write!(f, "    {async_}fn {}(&mut self", function_name(&wit.name)).unwrap();
+    if let Some(handle) = handle {
+        write!(f, ", self_: {handle}").unwrap();
+    }
+    for (name, ty) in &wit.params {
+        write!(f, ", {}: {}", function_name(name), host_type(ty)).unwrap();
+    }
+    let output = wit.output.as_ref().map_or("()".to_string(), host_type);
+    writeln!(f, ") -> wasmtime::Result<{output}> {{").unwrap();
+    f
+}
+
+fn case_expr(variant: &Variant, case: &str) -> String {
+    let has_payload = variant
+        .cases
+        .iter()
+        .any(|(name, payload)| name == case && payload.is_some());
+    if has_payload {
+        format!("Self::{}(message)", bindgen_name(case))
+    } else {
+        format!("Self::{}", bindgen_name(case))
+    }
+}
+
+/// The name `bindgen!` gives a WIT type, e.g. `texture2-d` is `Texture2D`
+///
+/// Like `bindgen!` and `wit-bindgen`, this goes through `heck`, which keeps
+/// digits in the word they are in.
+pub fn bindgen_name(wit_name: &str) -> String {
+    wit_name.trim_start_matches('%').to_upper_camel_case()
+}
+
+/// The name `bindgen!` gives a WIT function or parameter, e.g. `from-rgba8`
+/// is `from_rgba8`
+pub fn function_name(wit_name: &str) -> String {
+    let name = wit_name.trim_start_matches('%').to_snake_case();
+    // `bindgen!` appends `_` to Rust keywords, e.g. `%type` is `type_`
+    if RUST_KEYWORDS.contains(&name.as_str()) {
+        format!("{name}_")
+    } else {
+        name
+    }
+}
+
+/// Words `bindgen!` can't use as identifiers
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// The Rust type `bindgen!` uses for a WIT type
+pub fn host_type(ty: &WitType) -> String {
+    match &ty.kind {
+        WitTypeKind::U8 => "u8".to_string(),
+        WitTypeKind::U16 => "u16".to_string(),
+        WitTypeKind::U32 => "u32".to_string(),
+        WitTypeKind::U64 => "u64".to_string(),
+        WitTypeKind::S8 => "i8".to_string(),
+        WitTypeKind::S16 => "i16".to_string(),
+        WitTypeKind::S32 => "i32".to_string(),
+        WitTypeKind::S64 => "i64".to_string(),
+        WitTypeKind::Float32 => "f32".to_string(),
+        WitTypeKind::Float64 => "f64".to_string(),
+        WitTypeKind::Char => "char".to_string(),
+        WitTypeKind::Bool => "bool".to_string(),
+        WitTypeKind::String => "String".to_string(),
+        WitTypeKind::Tuple(types) => {
+            let types = types.iter().map(host_type).collect::<Vec<_>>();
+            match types.as_slice() {
+                [ty] => format!("({ty},)"),
+                _ => format!("({})", types.join(", ")),
+            }
+        }
+        WitTypeKind::List(ty) => format!("Vec<{}>", host_type(ty)),
+        WitTypeKind::Option(ty) => format!("Option<{}>", host_type(ty)),
+        WitTypeKind::Result { ok, err } => format!(
+            "Result<{}, {}>",
+            ok.as_deref().map(host_type).unwrap_or("()".to_string()),
+            err.as_deref().map(host_type).unwrap_or("()".to_string()),
+        ),
+        WitTypeKind::Named(name) => format!("wit::{}", bindgen_name(name)),
+        WitTypeKind::Record(Record { name, .. })
+        | WitTypeKind::Variant(Variant { name, .. })
+        | WitTypeKind::Enum(Enum { name, .. })
+        | WitTypeKind::Flags(Flags { name, .. })
+        | WitTypeKind::TypeAlias(TypeAlias { name, .. }) => format!("wit::{}", bindgen_name(name)),
+        WitTypeKind::Resource(name) | WitTypeKind::Borrow(name) => {
+            format!("wasmtime::component::Resource<wit::{}>", bindgen_name(name))
+        }
+        WitTypeKind::Iterator(_) => format!(
+            "wasmtime::component::Resource<wit::{}>",
+            bindgen_name(&ty.kind.print_ref())
+        ),
+    }
+}
+
+/// How the library's type is written in the host code
+///
+/// Paths are resolved through the public paths of the library, falling back
+/// to the path as written.
+pub fn rust_type(
+    paths: &HashMap<rustdoc_types::Id, Vec<String>>,
+    ty: &query::Item<&rustdoc_types::Type>,
+) -> String {
+    match ty.type_kind() {
+        query::TypeKind::Primitive(primitive) => primitive.to_string(),
+        query::TypeKind::Generic(generic) => generic.to_string(),
+        query::TypeKind::ResolvedPath(path) => {
+            let prelude = [
+                (STRING_PATH, "String"),
+                (OPTION_PATH, "Option"),
+                (RESULT_PATH, "Result"),
+                (VEC_PATH, "Vec"),
+                (BOX_PATH, "Box"),
+            ];
+            let prelude = prelude
+                .iter()
+                .find_map(|(std, name)| path.is(std).then_some(*name));
+            let name = match (prelude, paths.get(path.id()), path.summary()) {
+                (Some(name), _, _) => name.to_string(),
+                (None, Some(public), _) => public.join("::"),
+                (None, None, Some(summary)) => summary
+                    .path
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, segment)| match segment.as_str() {
+                        // not every crate has `extern crate alloc`
+                        "core" | "alloc" if idx == 0 => "std",
+                        segment => segment,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("::"),
+                (None, None, None) => path.name().to_string(),
+            };
+            let args = ty
+                .type_args()
+                .map(|arg| rust_type(paths, &arg))
+                .collect::<Vec<_>>();
+            if args.is_empty() {
+                name
+            } else {
+                format!("{name}<{}>", args.join(", "))
+            }
+        }
+        query::TypeKind::BorrowedRef(borrowed) => format!(
+            "&{}{}",
+            if borrowed.is_mutable() { "mut " } else { "" },
+            rust_type(paths, &borrowed.type_())
+        ),
+        query::TypeKind::Tuple(types) => {
+            let types = types
+                .types()
+                .map(|ty| rust_type(paths, &ty))
+                .collect::<Vec<_>>();
+            match types.as_slice() {
+                [ty] => format!("({ty},)"),
+                _ => format!("({})", types.join(", ")),
+            }
+        }
+        query::TypeKind::Slice(ty) => format!("[{}]", rust_type(paths, &ty)),
+        query::TypeKind::Array(array) => {
+            format!("[{}; {}]", rust_type(paths, &array.type_()), array.len())
+        }
+        query::TypeKind::QualifiedPath(qpath) => match qpath.resolve(None) {
+            Some(ty) => rust_type(paths, &ty),
+            None => "_".to_string(),
+        },
+        _ => "_".to_string(),
+    }
+}
+
+/// What converting a `bindgen!` value does when a list doesn't have the
+/// length of the Rust array it stands for
+#[derive(Clone, Copy, PartialEq)]
+enum Failure {
+    /// Return a `ConversionError` with `?`, in `TryFrom` impls
+    Propagate,
+    /// Return the `ConversionError` as a `wasmtime::Error` with `?`, trapping
+    /// the guest, in host functions
+    Trap,
+    /// Panic, where the signature is the library's, e.g. in trait adapters
+    Panic,
+}
+
+impl Failure {
+    /// How `ConversionError` is named where the conversion is written
+    fn error(self) -> &'static str {
+        match self {
+            Failure::Trap => "conversions::ConversionError",
+            Failure::Propagate | Failure::Panic => "ConversionError",
+        }
+    }
+}
+
+impl HostCode {
+    /// The arguments passing the parameters of `wit` to the library's
+    /// `function`, writing to `f` the statements taking the resources among
+    /// them out of the resource table
+    ///
+    /// Owned handles are deleted before any is borrowed, as borrows hold the
+    /// table. `receiver`, the type of `self_` of resource methods, is borrowed
+    /// as `object`.
+    fn args(
+        &self,
+        f: &mut String,
+        receiver: Option<&query::Item<&rustdoc_types::Type>>,
+        function: &query::Item<&rustdoc_types::Function>,
+        wit: &WitFunction,
+    ) -> Vec<String> {
+        let mut borrows = Vec::new();
+        if let Some(receiver) = receiver {
+            let get = table_get(receiver);
+            borrows.push(format!("let object = self.table.{get}(&self_)?;"));
+        }
+        let mut args = Vec::new();
+        for ((_, ty), (name, wit_ty)) in function.params().zip(&wit.params) {
+            let name = function_name(name);
+            let get = table_get(&ty);
+            let inner = match &wit_ty.kind {
+                WitTypeKind::Option(inner) => Some(&inner.kind),
+                _ => None,
+            };
+            match (&wit_ty.kind, inner) {
+                (WitTypeKind::Resource(_), _) => {
+                    writeln!(f, "        let {name} = self.table.delete({name})?;").unwrap()
+                }
+                (WitTypeKind::Borrow(_), _) => {
+                    borrows.push(format!("let {name} = self.table.{get}(&{name})?;"));
+                    // the table holds `Box<dyn Trait + Send>`
+                    if let query::TypeKind::BorrowedRef(borrowed) = ty.type_kind() {
+                        if let query::TypeKind::DynTrait(_) = borrowed.type_().type_kind() {
+                            let mut_ = if borrowed.is_mutable() { "mut " } else { "" };
+                            args.push(format!("&{mut_}**{name}"));
+                            continue;
+                        }
+                    }
+                }
+                (_, Some(WitTypeKind::Resource(_))) => writeln!(
+                    f,
+                    "        let {name} = match {name} {{ Some({name}) => Some(self.table.delete({name})?), None => None }};"
+                )
+                .unwrap(),
+                (_, Some(WitTypeKind::Borrow(_))) => borrows.push(format!(
+                    "let {name} = match &{name} {{ Some({name}) => Some(self.table.{get}({name})?), None => None }};"
+                )),
+                _ => {
+                    args.push(self.rust_arg(&name, &ty));
+                    continue;
+                }
+            }
+            args.push(name);
+        }
+        for borrow in borrows {
+            writeln!(f, "        {borrow}").unwrap();
+        }
+        args
+    }
+
+    /// Convert the `bindgen!` value `name` to the Rust parameter type `ty`
+    fn rust_arg(&self, name: &str, ty: &query::Item<&rustdoc_types::Type>) -> String {
+        match ty.type_kind() {
+            query::TypeKind::BorrowedRef(borrowed) => {
+                let inner = borrowed.type_();
+                match inner.type_kind() {
+                    query::TypeKind::Primitive(_) => format!("&{name}"),
+                    query::TypeKind::Slice(element) => match element.type_kind() {
+                        query::TypeKind::Primitive(_) => format!("&{name}"),
+                        _ => format!("&{name}.into_iter().map(Into::into).collect::<Vec<_>>()"),
+                    },
+                    _ => format!("&{}", self.rust_arg(name, &inner)),
+                }
+            }
+            _ => self.rust_value(name, ty, Failure::Trap),
+        }
+    }
+
+    /// Convert the owned `bindgen!` value `expr` to the Rust type `ty`, through
+    /// options, lists, arrays, tuples, boxes and results
+    fn rust_value(
+        &self,
+        expr: &str,
+        ty: &query::Item<&rustdoc_types::Type>,
+        failure: Failure,
+    ) -> String {
+        let args = ty.type_args().collect::<Vec<_>>();
+        let propagate = |ty: &query::Item<&rustdoc_types::Type>| {
+            failure != Failure::Panic && self.is_fallible(ty)
+        };
+        match ty.type_kind() {
+            query::TypeKind::Primitive(_) => expr.to_string(),
+            query::TypeKind::ResolvedPath(path) if path.is(STRING_PATH) => expr.to_string(),
+            query::TypeKind::ResolvedPath(path) if path.is(BOX_PATH) => match args.first() {
+                Some(inner) => format!("Box::new({})", self.rust_value(expr, inner, failure)),
+                None => format!("{expr}.into()"),
+            },
+            query::TypeKind::ResolvedPath(path) if path.is(OPTION_PATH) => {
+                let Some(inner) = args.first() else {
+                    return format!("{expr}.map(Into::into)");
+                };
+                let value = self.rust_value("value", inner, failure);
+                if propagate(inner) {
+                    format!("match {expr} {{ Some(value) => Some({value}), None => None }}")
+                } else {
+                    match map_fn("value", &value) {
+                        Some(f) => format!("{expr}.map({f})"),
+                        None => expr.to_string(),
+                    }
+                }
+            }
+            query::TypeKind::ResolvedPath(path) if path.is(RESULT_PATH) => {
+                let convert = |var: &str, ty: Option<&query::Item<&rustdoc_types::Type>>| {
+                    ty.map_or(var.to_string(), |ty| self.rust_value(var, ty, failure))
+                };
+                let (ok, err) = (
+                    convert("value", args.first()),
+                    convert("error", args.get(1)),
+                );
+                if ok == "value" && err == "error" {
+                    expr.to_string()
+                } else {
+                    format!("match {expr} {{ Ok(value) => Ok({ok}), Err(error) => Err({err}) }}")
+                }
+            }
+            query::TypeKind::ResolvedPath(path) if path.is(VEC_PATH) => match args.first() {
+                Some(item) if propagate(item) => self.rust_list(expr, item, failure),
+                Some(item) => match map_fn("item", &self.rust_value("item", item, failure)) {
+                    Some(f) => format!("{expr}.into_iter().map({f}).collect()"),
+                    None => expr.to_string(),
+                },
+                None => format!("{expr}.into_iter().map(Into::into).collect()"),
+            },
+            query::TypeKind::Array(array) => {
+                let item = array.type_();
+                let list = match map_fn("item", &self.rust_value("item", &item, failure)) {
+                    _ if propagate(&item) => self.rust_list(expr, &item, failure),
+                    Some(f) => format!("{expr}.into_iter().map({f}).collect::<Vec<_>>()"),
+                    None => expr.to_string(),
+                };
+                let len = array.len();
+                match failure {
+                    Failure::Propagate | Failure::Trap => format!(
+                        "<[_; {len}]>::try_from({list}).map_err(|list: Vec<_>| {}::Length {{ expected: {len}, found: list.len() }})?",
+                        failure.error()
+                    ),
+                    Failure::Panic => format!(
+                        "<[_; {len}]>::try_from({list}).unwrap_or_else(|list: Vec<_>| panic!(\"expected {len} items, found {{}}\", list.len()))"
+                    ),
+                }
+            }
+            query::TypeKind::Tuple(types) => {
+                let types = types.types().collect::<Vec<_>>();
+                tuple(expr, &types, |var, ty| self.rust_value(var, ty, failure))
+            }
+            query::TypeKind::ResolvedPath(_) if self.is_fallible(ty) => match failure {
+                Failure::Propagate | Failure::Trap => format!("{expr}.try_into()?"),
+                Failure::Panic => {
+                    format!("{expr}.try_into().expect(\"lists have the lengths of the arrays\")")
+                }
+            },
+            _ => format!("{expr}.into()"),
+        }
+    }
+
+    /// Convert the `bindgen!` list `expr` to a `Vec` of `item`s, returning the
+    /// first `ConversionError` with `?`
+    fn rust_list(
+        &self,
+        expr: &str,
+        item: &query::Item<&rustdoc_types::Type>,
+        failure: Failure,
+    ) -> String {
+        format!(
+            "{expr}.into_iter().map(|item| Ok({})).collect::<Result<Vec<_>, {}>>()?",
+            self.rust_value("item", item, failure),
+            failure.error()
+        )
+    }
+}
+
+/// Convert the library value `expr` of type `ty` to its `bindgen!` type
+fn from_rust(expr: &str, ty: &query::Item<&rustdoc_types::Type>) -> String {
+    let args = ty.type_args().collect::<Vec<_>>();
+    let items = |item: Option<&query::Item<&rustdoc_types::Type>>| {
+        item.and_then(|item| map_fn("item", &from_rust("item", item)))
+            .unwrap_or("Into::into".to_string())
+    };
+    match ty.type_kind() {
+        query::TypeKind::Primitive(_) => expr.to_string(),
+        query::TypeKind::BorrowedRef(borrowed) => match borrowed.type_().type_kind() {
+            query::TypeKind::Primitive("str") => format!("{expr}.to_string()"),
+            _ => from_rust(&format!("{expr}.clone()"), &borrowed.type_()),
+        },
+        query::TypeKind::ResolvedPath(path) if path.is(STRING_PATH) => expr.to_string(),
+        query::TypeKind::ResolvedPath(path) if path.is(BOX_PATH) => match args.first() {
+            Some(inner) => from_rust(&format!("(*{expr})"), inner),
+            None => format!("{expr}.into()"),
+        },
+        query::TypeKind::ResolvedPath(path) if path.is(OPTION_PATH) => {
+            let value = args.first().map(|inner| from_rust("value", inner));
+            match value.map_or(Some("Into::into".to_string()), |value| {
+                map_fn("value", &value)
+            }) {
+                Some(f) => format!("{expr}.map({f})"),
+                None => expr.to_string(),
+            }
+        }
+        query::TypeKind::ResolvedPath(path) if path.is(RESULT_PATH) => {
+            let convert = |var: &str, ty: Option<&query::Item<&rustdoc_types::Type>>| {
+                ty.map_or(Some("Into::into".to_string()), |ty| {
+                    map_fn(var, &from_rust(var, ty))
+                })
+            };
+            let mut f = expr.to_string();
+            if let Some(ok) = convert("value", args.first()) {
+                write!(f, ".map({ok})").unwrap();
+            }
+            if let Some(err) = convert("error", args.get(1)) {
+                write!(f, ".map_err({err})").unwrap();
+            }
+            f
+        }
+        query::TypeKind::ResolvedPath(path) if path.is(VEC_PATH) => {
+            match args
+                .first()
+                .map(|item| map_fn("item", &from_rust("item", item)))
+            {
+                Some(None) => expr.to_string(),
+                _ => format!("{expr}.into_iter().map({}).collect()", items(args.first())),
+            }
+        }
+        query::TypeKind::Slice(item) => {
+            format!("{expr}.into_iter().map({}).collect()", items(Some(&item)))
+        }
+        query::TypeKind::Array(array) => format!(
+            "{expr}.into_iter().map({}).collect()",
+            items(Some(&array.type_()))
+        ),
+        query::TypeKind::Tuple(types) => {
+            let types = types.types().collect::<Vec<_>>();
+            tuple(expr, &types, from_rust)
+        }
+        query::TypeKind::ImplTrait(bounds) => match iterator_items(expr, ty) {
+            Some(items) => format!("{items}.collect()"),
+            None if bounds.has_bound(DISPLAY_PATH) => format!("{expr}.to_string()"),
+            None => format!("{expr}.into()"),
+        },
+        _ => format!("{expr}.into()"),
+    }
+}
+
+/// The function converting `var` to `converted` for `map`, `None` if that
+/// doesn't convert it
+fn map_fn(var: &str, converted: &str) -> Option<String> {
+    if converted == var {
+        None
+    } else if converted == format!("{var}.into()") {
+        Some("Into::into".to_string())
+    } else {
+        Some(format!("|{var}| {converted}"))
+    }
+}
+
+/// Convert the tuple `expr` element by element with `convert`
+fn tuple(
+    expr: &str,
+    types: &[query::Item<&rustdoc_types::Type>],
+    convert: impl Fn(&str, &query::Item<&rustdoc_types::Type>) -> String,
+) -> String {
+    let names = (0..types.len())
+        .map(|idx| format!("t{idx}"))
+        .collect::<Vec<_>>();
+    let converted = names
+        .iter()
+        .zip(types)
+        .map(|(name, ty)| convert(name, ty))
+        .collect::<Vec<_>>();
+    if names == converted {
+        return expr.to_string();
+    }
+    format!(
+        "{{ let ({},) = {expr}; ({},) }}",
+        names.join(", "),
+        converted.join(", ")
+    )
+}
+
+impl HostCode {
+    /// Whether a `bindgen!` value might not fit the Rust type `ty`, as a list
+    /// within it might not have the length of the array it stands for, an enum
+    /// within it might be `#[non_exhaustive]`, or an arena within it might have
+    /// invalid indices
+    pub fn is_fallible(&self, ty: &query::Item<&rustdoc_types::Type>) -> bool {
+        self.fallible(ty, &mut Vec::new())
+    }
+
+    /// See [`is_fallible`], `seen` being the structs already looked into
+    fn fallible(
+        &self,
+        ty: &query::Item<&rustdoc_types::Type>,
+        seen: &mut Vec<rustdoc_types::Id>,
+    ) -> bool {
+        match ty.type_kind() {
+            query::TypeKind::Array(_) => true,
+            query::TypeKind::Slice(item) => self.fallible(&item, seen),
+            query::TypeKind::BorrowedRef(borrowed) => self.fallible(&borrowed.type_(), seen),
+            query::TypeKind::Tuple(types) => types.types().any(|ty| self.fallible(&ty, seen)),
+            query::TypeKind::ResolvedPath(path) => {
+                if ty.type_args().any(|arg| self.fallible(&arg, seen)) {
+                    return true;
+                }
+                if let Some(enum_) = path.enum_item() {
+                    if enum_.is_non_exhaustive() {
+                        return true;
+                    }
+                    if seen.contains(path.id()) {
+                        return false;
+                    }
+                    seen.push(path.id().clone());
+                    return enum_.variants().any(|variant| match variant.variantkind() {
+                        query::VariantItemKind::Plain(_) => false,
+                        query::VariantItemKind::Tuple(tuple) => {
+                            tuple.fields().flatten().any(|ty| self.fallible(&ty, seen))
+                        }
+                        query::VariantItemKind::Struct(struct_) => {
+                            struct_.fields().any(|(_, ty)| self.fallible(&ty, seen))
+                        }
+                    });
+                }
+                let Some(struct_) = path.struct_item() else {
+                    return false;
+                };
+                if seen.contains(path.id()) {
+                    return false;
+                }
+                seen.push(path.id().clone());
+                // arenas convert back with `TryFrom`, as their indices are
+                // checked, and other recursive structs are resources
+                if crate::is_recursive(&struct_) {
+                    return self.arenas.contains(path.id());
+                }
+                match struct_.struct_kind() {
+                    query::StructItemKind::StructPlain(plain) if !plain.fields_stripped() => {
+                        plain.fields().any(|(_, ty)| self.fallible(&ty, seen))
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The items of the library iterator `expr` converted to their `bindgen!` type,
+/// if `ty` is `impl Iterator<Item = T>`
+fn iterator_items(expr: &str, ty: &query::Item<&rustdoc_types::Type>) -> Option<String> {
+    let query::TypeKind::ImplTrait(bounds) = ty.type_kind() else {
+        return None;
+    };
+    let item = bounds.binding(ITERATOR_PATH, "Item")?;
+    Some(format!("{expr}.map(|item| {})", from_rust("item", &item)))
+}
@@ -1,27 +1,119 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fmt::Write,
     // io::Write,
     fs,
+    path::PathBuf,
 };
 
 use clap::Parser;
 use colored::{ColoredString, Colorize};
+use config::RecursiveMode;
 use convert_case::{Case, Casing};
 use io_adapters::WriteExtension;
-use query::StructItemKind;
+use query::{ItemEnumExt, StructItemKind};
 use rustdoc_types::{
     Crate, Function, GenericArg, GenericArgs, Id, Impl, Item, ItemEnum, ItemSummary, Struct,
     StructKind, Type, Visibility,
 };
 
+mod config;
+mod guest;
+mod host;
+mod names;
 pub mod query;
 
+/// Fully qualified paths of the std types that have a WIT equivalent
+const STRING_PATH: &[&str] = &["alloc", "string", "String"];
+const OPTION_PATH: &[&str] = &["core", "option", "Option"];
+const RESULT_PATH: &[&str] = &["core", "result", "Result"];
+const VEC_PATH: &[&str] = &["alloc", "vec", "Vec"];
+const BOX_PATH: &[&str] = &["alloc", "boxed", "Box"];
+
+const ERROR_PATH: &[&str] = &["core", "error", "Error"];
+const DISPLAY_PATH: &[&str] = &["core", "fmt", "Display"];
+const INTO_PATH: &[&str] = &["core", "convert", "Into"];
+const ITERATOR_PATH: &[&str] = &["core", "iter", "traits", "iterator", "Iterator"];
+const SIZED_PATH: &[&str] = &["core", "marker", "Sized"];
+const SEND_PATH: &[&str] = &["core", "marker", "Send"];
+const FUTURE_PATH: &[&str] = &["core", "future", "future", "Future"];
+
+/// The std traits of [`config::Helper`]s
+const HELPERS: &[(&[&str], config::Helper)] = &[
+    (DISPLAY_PATH, config::Helper::ToString),
+    (&["core", "fmt", "Debug"], config::Helper::DebugString),
+    (&["core", "default", "Default"], config::Helper::Default),
+    (&["core", "clone", "Clone"], config::Helper::Clone),
+    (&["core", "str", "traits", "FromStr"], config::Helper::Parse),
+];
+
+/// Conversion traits and the WIT name of their functions, see [`conversion`]
+const CONVERSIONS: &[(&[&str], &str)] = &[
+    (&["core", "convert", "From"], "from"),
+    (INTO_PATH, "into"),
+    (&["core", "convert", "TryFrom"], "try-from"),
+    (&["core", "convert", "TryInto"], "try-into"),
+];
+
+/// Operator traits, the WIT name of their functions and the trait method, see [`operator`]
+const OPERATORS: &[(&[&str], &str, &str)] = &[
+    (&["core", "ops", "arith", "Add"], "add", "add"),
+    (&["core", "ops", "arith", "Sub"], "sub", "sub"),
+    (&["core", "ops", "arith", "Mul"], "mul", "mul"),
+    (&["core", "ops", "arith", "Div"], "div", "div"),
+    (&["core", "ops", "arith", "Rem"], "rem", "rem"),
+    (&["core", "ops", "arith", "Neg"], "neg", "neg"),
+    (&["core", "ops", "bit", "Not"], "not", "not"),
+    (&["core", "ops", "bit", "BitAnd"], "bit-and", "bitand"),
+    (&["core", "ops", "bit", "BitOr"], "bit-or", "bitor"),
+    (&["core", "ops", "bit", "BitXor"], "bit-xor", "bitxor"),
+    (&["core", "ops", "bit", "Shl"], "shl", "shl"),
+    (&["core", "ops", "bit", "Shr"], "shr", "shr"),
+    (&["core", "ops", "index", "Index"], "index", "index"),
+    (&["core", "cmp", "PartialEq"], "eq", "eq"),
+    (&["core", "cmp", "PartialOrd"], "cmp", "partial_cmp"),
+];
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-pub struct Args;
+pub struct Args {
+    /// JSON file of settings, see `config.rs`
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Export async functions as WIT `async func`s, from the component model
+    /// async proposal
+    #[arg(long = "async")]
+    async_: bool,
+    /// Also generate a world importing or exporting the library, or both
+    #[arg(long, value_enum)]
+    world: Option<WorldKind>,
+    /// Worlds of dependencies to `include` in the generated worlds, e.g.
+    /// `maxiquad:glam/glam-host`
+    #[arg(long, requires = "world")]
+    include: Vec<String>,
+    /// Also write a guest crate using the library through `wit-bindgen` to
+    /// this directory, with wrappers restoring the Rust names
+    #[arg(long)]
+    guest: Option<PathBuf>,
+    /// Write the conversions between the library's types and the `bindgen!`
+    /// types to this file, as a module to put next to the `wit` module,
+    /// instead of printing them as a `conversions` module
+    #[arg(long)]
+    conversions: Option<PathBuf>,
+}
+
+/// Which side of a component provides the library
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum WorldKind {
+    /// The host provides the library, e.g. with wasmtime, so guests import it
+    Import,
+    /// A guest provides the library to other components
+    Export,
+    /// Both worlds, and a world including both
+    Combined,
+}
 
-pub fn main(_args: Args) {
+pub fn main(args: Args) {
     // TODO: should be passed through args
     let file = fs::read_to_string("macroquad.json").unwrap();
     let namespace = "maxiquad";
@@ -49,306 +141,2956 @@ pub fn main(_args: Args) {
 
     println!("module count = {}", krate.all_modules().count());
 
-    process_module(root_crate_id, root, 0);
-}
-
-fn process_module(root_crate_id: u32, module: query::Item<&rustdoc_types::Module>, depth: usize) {
-    let indent = " ".repeat(depth * 4);
-    for struct_ in module.structs() {
-        let struct_name = struct_.name().to_case(Case::Kebab);
-        let wit = 'wit: {
-            match struct_.struct_kind() {
-                StructItemKind::StructPlain(plain) => {
-                    if plain.fields_stripped() {
-                        if plain.impls().count() > 0 {
-                            println!("{indent}resource {} {{", struct_name);
-                            for impl_ in plain.impls() {
-                                println!(
-                                    "{indent}    impl {:?}",
-                                    impl_.maybe_name().map(|s| s.to_case(Case::Kebab))
-                                );
-                            }
-                            println!("{indent}}}");
-                        } else {
-                            println!("{indent}resource {};", struct_name);
-                        }
+    let mut paths = HashMap::new();
+    public_paths(&root, &[name.to_string()], &mut paths);
 
-                        break 'wit WitType {
-                            kind: WitTypeKind::Resource(struct_name),
-                            functions: (),
-                            source: Source::Local(plain.data.id.clone()),
-                        };
-                    }
+    let config = match &args.config {
+        Some(path) => {
+            let file = fs::read_to_string(path).expect("config file is readable");
+            serde_json::from_str(&file).expect("config file is valid")
+        }
+        None => config::Config::default(),
+    };
 
-                    println!("{indent}record {} {{", struct_name);
-                    let mut fields = Vec::with_capacity(plain.fields().count());
-                    for (field_name, field_type) in plain.fields() {
-                        match field_type.type_kind() {
-                            query::TypeKind::ResolvedPath(path) => {
-                                if let Some(item) = path.item() {
-                                    if item.data.crate_id == root_crate_id {
-                                        println!(
-                                            "{indent}    {field_name}: {path:?}, // local",
-                                            path = item.maybe_name().unwrap(),
-                                        );
-                                        fields.push((
-                                            field_name.to_string(),
-                                            WitType {
-                                                kind: todo!("depends on the path"),
-                                                functions: (),
-                                                source: todo!(),
-                                            },
-                                        ))
-                                    } else {
-                                        todo!();
-                                    }
-                                } else if let Some(summary) = path.summary() {
-                                    if summary.crate_id == root_crate_id {
-                                        println!(
-                                            "{indent}    {field_name}: {path}, // local",
-                                            path = summary.path.join("::"),
-                                        );
-                                        fields.push((
-                                            field_name.to_string(),
-                                            WitType {
-                                                kind: todo!(),
-                                                functions: todo!(),
-                                                source: todo!(),
-                                            },
-                                        ));
-                                    }
-                                } else {
-                                    todo!();
-                                }
-                            }
-                            query::TypeKind::DynTrait(_) => todo!("query::TypeKind::DynTrait"),
-                            // if it contains a generic, make it a resource
-                            query::TypeKind::Generic(_) => {
-                                println!(
-                                    "{indent}    {field_name}: unsupported, // UNSUPPORTED: query::TypeKind::Generic"
-                                );
-                                break 'wit WitType {
-                                    kind: WitTypeKind::Resource(struct_name),
-                                    functions: (),
-                                    source: Source::Foreign(None),
-                                };
-                            }
-                            query::TypeKind::Primitive(primitive) => {
-                                println!("{indent}    {field_name}: {primitive},");
-                                fields.push((
-                                    field_name.to_string(),
-                                    WitType {
-                                        kind: WitTypeKind::from_rust_type(primitive)
-                                            .expect("known primitive"),
-                                        functions: (),
-                                        source: Source::Foreign(None),
-                                    },
-                                ));
-                            }
-                            query::TypeKind::FunctionPointer(_) => {
-                                todo!("query::TypeKind::FunctionPointer")
-                            }
-                            query::TypeKind::Tuple(_) => todo!("query::TypeKind::Tuple"),
-                            query::TypeKind::Slice(_) => todo!("query::TypeKind::Slice"),
-                            query::TypeKind::Array(_) => todo!("query::TypeKind::Array"),
-                            query::TypeKind::ImplTrait(_) => todo!("query::TypeKind::ImplTrait"),
-                            query::TypeKind::Infer => todo!("query::TypeKind::Infer"),
-                            query::TypeKind::RawPointer(_) => todo!("query::TypeKind::RawPointer"),
-                            // if it contains a borrow, make it a resource
-                            query::TypeKind::BorrowedRef(_) => {
-                                println!(
-                                    "{indent}    {field_name}: unsupported, // UNSUPPORTED: query::TypeKind::BorrowedRef"
-                                );
-                                break 'wit WitType {
-                                    kind: WitTypeKind::Resource(struct_name),
-                                    functions: (),
-                                    source: todo!(),
-                                };
-                            }
-                            query::TypeKind::QualifiedPath(_) => {
-                                todo!("query::TypeKind::QualifiedPath")
+    let mut ctx = Context {
+        root_crate_id,
+        paths,
+        config,
+        async_: args.async_,
+        host: host::HostCode::default(),
+        guest: guest::GuestCode::default(),
+        trait_objects: BTreeMap::new(),
+        iterators: BTreeMap::new(),
+        self_type: None,
+        trait_interfaces: Vec::new(),
+        interfaces: Vec::new(),
+        type_interfaces: HashMap::new(),
+        defined_types: None,
+        resources: HashSet::new(),
+    };
+    find_types(&mut ctx, &root, name);
+    let arenas = arenas(&ctx, &krate);
+    ctx.host.set_arenas(arenas);
+    process_module(&mut ctx, &root, &[name.to_string()], None);
+    process_crate(&mut ctx, &krate);
+
+    // the guest crate targets the host world, in the same WIT as printed
+    let kind = match (args.world, &args.guest) {
+        (None, Some(_)) => Some(WorldKind::Import),
+        (Some(WorldKind::Export), Some(_)) => Some(WorldKind::Combined),
+        (kind, _) => kind,
+    };
+    let worlds = kind.map_or_else(Vec::new, |kind| worlds(&ctx, name, kind, &args.include));
+    let wit = print_wit(&ctx, &worlds);
+    println!("{wit}");
+
+    println!("{}", ctx.host.print());
+    let conversions = ctx.host.print_conversions().to_string();
+    match &args.conversions {
+        Some(path) => fs::write(path, conversions).expect("conversions file is writable"),
+        None => println!(
+            "pub mod conversions {{\n{}\n}}",
+            indented(conversions.trim_end(), "    ")
+        ),
+    }
+
+    if let Some(dir) = &args.guest {
+        // the host world comes first
+        let world = &worlds[0];
+        let user = user_interfaces(&ctx);
+        let interfaces = ctx
+            .interfaces
+            .iter()
+            .map(|interface| interface.name.clone())
+            .filter(|name| !user.contains(name))
+            .collect::<Vec<_>>();
+        let files = ctx.guest.crate_files(
+            namespace,
+            name,
+            krate.version().unwrap(),
+            &interfaces,
+            &wit,
+            &world.name,
+        );
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().expect("files are in the crate"))
+                .expect("guest crate directory is writable");
+            fs::write(&path, contents).expect("guest crate directory is writable");
+        }
+    }
+}
+
+/// Add what is only known once every module has been walked: the operators
+/// and conversions of the crate's trait impls, the resources of trait objects
+/// and iterators
+///
+/// Operators, conversions and resources go in the interface of the type they
+/// belong to.
+fn process_crate(ctx: &mut Context, krate: &query::CrateQuery) {
+    let mut operators = Vec::new();
+    for impl_ in krate
+        .all_trait_impls()
+        .filter(|impl_| !impl_.is_synthetic() && !impl_.is_blanket())
+    {
+        let Some(&(_, op, method)) = OPERATORS
+            .iter()
+            .find(|(path, ..)| impl_.trait_().is_some_and(|trait_| trait_.is(path)))
+        else {
+            continue;
+        };
+        let query::TypeKind::ResolvedPath(self_type) = impl_.for_().type_kind() else {
+            continue;
+        };
+        // only the operators of exported types
+        let Some(self_path) = ctx.paths.get(self_type.id()).map(|path| path.join("::")) else {
+            continue;
+        };
+        ctx.self_type = Some(self_type.id().clone());
+        let owner = owner_interface(ctx, &[self_type.id().clone()]);
+        let operator = operator(ctx, &impl_, op, method)
+            .and_then(|operator| defined(ctx, &operator.wit).map(|()| operator));
+        match operator {
+            Ok(operator) => operators.push((owner, self_path, op, operator)),
+            Err(reason) => {
+                let text = format!(
+                    "// {}-{op}: UNSUPPORTED: {reason}",
+                    names::ident(self_type.name())
+                );
+                extend_interface(ctx, &owner, &text, []);
+            }
+        }
+    }
+    ctx.self_type = None;
+    // e.g. `vec2-mul-f32` and `vec2-mul-vec2` for `Mul<f32>` and `Mul<Vec2>`
+    let names = operators
+        .iter()
+        .map(|(.., operator)| operator.wit.name.clone())
+        .collect::<Vec<_>>();
+    for (.., operator) in &mut operators {
+        let wit = &mut operator.wit;
+        if names.iter().filter(|name| **name == wit.name).count() > 1 {
+            if let Some((_, rhs)) = wit.params.get(1) {
+                wit.name = wit_ident(&format!("{}-{}", wit.name, rhs.kind.print_ref()));
+            }
+        }
+    }
+    operators.sort_by(|(.., a), (.., b)| a.wit.name.cmp(&b.wit.name));
+    for (owner, self_path, op, operator) in &operators {
+        extend_interface(
+            ctx,
+            owner,
+            &operator.wit.print().to_string(),
+            operator.wit.types(),
+        );
+        ctx.host.set_interface(owner);
+        ctx.host.operator(&ctx.paths, self_path, op, operator);
+        // comparisons are derived by `wit-bindgen`, and `Index` has to return
+        // a reference
+        if let Some((["core", "ops", .., trait_], _, method)) = OPERATORS
+            .iter()
+            .find(|(_, name, _)| name == op)
+            .filter(|_| *op != "index")
+        {
+            ctx.guest.operator(trait_, method, &operator.wit);
+        }
+    }
+
+    let mut conversions = Vec::new();
+    for impl_ in krate
+        .all_trait_impls()
+        .filter(|impl_| !impl_.is_synthetic() && !impl_.is_blanket())
+    {
+        let Some(&(_, kind)) = CONVERSIONS
+            .iter()
+            .find(|(path, _)| impl_.trait_().is_some_and(|trait_| trait_.is(path)))
+        else {
+            continue;
+        };
+        ctx.self_type = match impl_.for_().type_kind() {
+            query::TypeKind::ResolvedPath(self_type) => Some(self_type.id().clone()),
+            _ => None,
+        };
+        let conversion = conversion(ctx, &impl_, kind).and_then(|conversion| match conversion {
+            Some(conversion) => defined(ctx, &conversion.wit).map(|()| Some(conversion)),
+            None => Ok(None),
+        });
+        match conversion {
+            // with the type of the impl, or e.g. `impl Into<Camera2D> for
+            // &Camera` goes with `Camera`
+            Ok(Some(conversion)) => {
+                let mut ids = ctx.self_type.iter().cloned().collect::<Vec<_>>();
+                conversion
+                    .wit
+                    .types()
+                    .for_each(|ty| local_types(ty, &mut ids));
+                conversions.push((owner_interface(ctx, &ids), conversion));
+            }
+            Ok(None) => {}
+            Err(reason) => {
+                let owner = owner_interface(ctx, ctx.self_type.as_slice());
+                let text = format!("// {kind} impl: UNSUPPORTED: {reason}");
+                extend_interface(ctx, &owner, &text, []);
+            }
+        }
+    }
+    ctx.self_type = None;
+    // e.g. `From<&str>` and `From<String>` are both `from-string`, preferably
+    // converting from the owned type
+    conversions.sort_by_key(|(_, conversion)| {
+        let borrowed = matches!(conversion.from.type_kind(), query::TypeKind::BorrowedRef(_));
+        (conversion.wit.name.clone(), borrowed)
+    });
+    conversions.dedup_by(|(_, a), (_, b)| a.wit.name == b.wit.name);
+    for (owner, conversion) in &conversions {
+        extend_interface(
+            ctx,
+            owner,
+            &conversion.wit.print().to_string(),
+            conversion.wit.types(),
+        );
+        ctx.host.set_interface(owner);
+        ctx.host.conversion(&ctx.paths, conversion);
+        ctx.guest
+            .conversion(&conversion.wit, conversion.error.is_some());
+    }
+
+    for (name, (user, id)) in ctx.trait_objects.clone() {
+        let Some(trait_) = krate.krate().index.get(&id).and_then(|item| {
+            let inner = item.inner.as_trait()?;
+            Some(query::Item::new(krate.krate(), item, inner))
+        }) else {
+            continue;
+        };
+        // the interface of the trait's module, if it has one
+        let owner = ctx
+            .paths
+            .get(&id)
+            .map(|path| interface_name(&path[..path.len() - 1]))
+            .filter(|owner| {
+                ctx.interfaces
+                    .iter()
+                    .any(|interface| interface.name == *owner)
+            })
+            .unwrap_or(user);
+        ctx.type_interfaces
+            .insert(id.clone(), (owner.clone(), name.clone()));
+        let (resource, methods, skipped) = trait_resource(ctx, &trait_, &name);
+        let mut text = resource.print().to_string();
+        for (method, reason) in skipped {
+            write!(text, "\n// {name}.{method}: UNSUPPORTED: {reason}").unwrap();
+        }
+        let types = methods.iter().flat_map(|(_, wit)| wit.types());
+        extend_interface(ctx, &owner, &text, types);
+        let rust_path = ctx
+            .paths
+            .get(&id)
+            .map_or(trait_.name().to_string(), |path| path.join("::"));
+        ctx.host.set_interface(&owner);
+        ctx.host.trait_object(&rust_path, &resource, &methods);
+    }
+
+    for (name, (owner, item)) in ctx.iterators.clone() {
+        let resource = iterator_resource(&name, &item);
+        extend_interface(ctx, &owner, &resource.print().to_string(), [&item]);
+        ctx.host.set_interface(&owner);
+        ctx.host.iterator(&resource, &item);
+    }
+}
+
+/// Check that the local types `wit` refers to are defined in an interface,
+/// which those of modules that aren't exported, or that can't be
+/// represented, aren't
+fn defined(ctx: &Context, wit: &WitFunction) -> Result<(), String> {
+    let mut undefined = None;
+    for ty in wit.types() {
+        ty.visit(&mut |ty| match &ty.source {
+            Source::Local(id) if !ctx.type_interfaces.contains_key(id) => {
+                undefined.get_or_insert_with(|| ty.kind.print_ref());
+            }
+            _ => {}
+        });
+    }
+    match undefined {
+        Some(name) => Err(format!("`{name}` isn't defined in an interface")),
+        None => Ok(()),
+    }
+}
+
+/// Whether the local type `id` is defined in an interface, see
+/// [`find_types`]
+fn is_defined(ctx: &Context, id: &Id) -> bool {
+    ctx.defined_types
+        .as_ref()
+        .is_none_or(|defined| defined.contains(id))
+}
+
+/// Walk the crate until the set of local types it defines settles, and keep
+/// it in `ctx`, so items referring to types that aren't defined, e.g. types
+/// of private modules or variants with such payloads, aren't exported
+///
+/// Each walk only leaves out more types, as it leaves out the ones using the
+/// types the previous one didn't define. Likewise, it only finds more
+/// resources, the structs holding resources found by the previous one, which
+/// `ctx.resources` keeps.
+fn find_types(ctx: &mut Context, root: &query::Item<&rustdoc_types::Module>, name: &str) {
+    loop {
+        let resources = ctx.resources.len();
+        process_module(ctx, root, &[name.to_string()], None);
+        let defined = ctx.type_interfaces.keys().cloned().collect::<HashSet<_>>();
+        let settled =
+            ctx.defined_types.as_ref() == Some(&defined) && ctx.resources.len() == resources;
+        ctx.defined_types = Some(defined);
+        ctx.host = host::HostCode::default();
+        ctx.guest = guest::GuestCode::default();
+        ctx.trait_objects.clear();
+        ctx.iterators.clear();
+        ctx.trait_interfaces.clear();
+        ctx.interfaces.clear();
+        ctx.type_interfaces.clear();
+        if settled {
+            break;
+        }
+    }
+}
+
+/// The WIT of the library: the interfaces of [`print_interfaces`], then
+/// `worlds`
+fn print_wit(ctx: &Context, worlds: &[World]) -> String {
+    let mut f = print_interfaces(ctx);
+    for world in worlds {
+        writeln!(f, "{}", world.print()).unwrap();
+    }
+    f
+}
+
+/// State shared while walking the crate
+struct Context {
+    root_crate_id: u32,
+    /// See [`public_paths`]
+    paths: HashMap<Id, Vec<String>>,
+    config: config::Config,
+    /// See [`Args::async_`]
+    async_: bool,
+    host: host::HostCode,
+    guest: guest::GuestCode,
+    /// Traits used as `dyn Trait`, and the interface first using them, by
+    /// resource name
+    trait_objects: BTreeMap<String, (String, Id)>,
+    /// The interface defining each iterator resource, the first one using
+    /// it, and its item type, by resource name
+    iterators: BTreeMap<String, (String, WitType)>,
+    /// The type `Self` stands for while mapping the items of an impl
+    self_type: Option<Id>,
+    /// Interfaces of the traits the library user implements
+    trait_interfaces: Vec<String>,
+    /// Interfaces of the modules, in the order they were walked
+    interfaces: Vec<ModuleInterface>,
+    /// The interface and WIT name of each local type
+    type_interfaces: HashMap<Id, (String, String)>,
+    /// The local types the interfaces define, see [`find_types`], or `None`
+    /// while finding them, when every local type is taken to be
+    defined_types: Option<HashSet<Id>>,
+    /// The local structs exported as resources, which are passed as handles
+    /// to their values in the host's resource table, see [`find_types`]
+    resources: HashSet<Id>,
+}
+
+/// The WIT definitions of a module, before the `use`s of the types of other
+/// modules are known
+struct ModuleInterface {
+    name: String,
+    /// See [`annotation`]
+    annotation: Option<String>,
+    body: String,
+    /// Local types the definitions refer to, see [`local_types`]
+    referenced: Vec<Id>,
+    /// Types without an `Id` the definitions refer to, e.g. iterator
+    /// resources, with the interface defining them
+    uses: Vec<(String, String)>,
+}
+
+/// Add the interface of `module`, and those of its submodules, to
+/// `ctx.interfaces`
+///
+/// `path` is the Rust path of `module`, e.g. `["macroquad", "camera"]`, and
+/// `gates` the lines to print above the interface, see [`annotation`].
+fn process_module(
+    ctx: &mut Context,
+    module: &query::Item<&rustdoc_types::Module>,
+    path: &[String],
+    gates: Option<String>,
+) {
+    let root_crate_id = ctx.root_crate_id;
+    let interface = interface_name(path);
+    let mut out = String::new();
+    // local types the definitions refer to, which may need a `use`
+    let mut referenced = Vec::new();
+    let mut uses = Vec::new();
+    ctx.host.set_interface(&interface);
+    let mut scope = names::Scope::default();
+    // type definitions, to be ordered by the types they refer to
+    let mut definitions = Vec::new();
+    for (name, struct_) in exported(module, |inner| inner.as_struct()) {
+        let mut rust_path = path.to_vec();
+        rust_path.push(name.clone());
+        let rust_path = rust_path.join("::");
+        if !is_public_path(ctx, &struct_.data.id, &rust_path) {
+            continue;
+        }
+        let mut out = String::new();
+        let start = referenced.len();
+        'item: {
+            let struct_name = names::ident(&name);
+            match annotation(ctx, &struct_, &rust_path) {
+                Ok(annotation) => print_annotation(&mut out, annotation.as_deref()),
+                Err(reason) => {
+                    writeln!(out, "// {struct_name}: SKIPPED: {reason}").unwrap();
+                    break 'item;
+                }
+            }
+            let struct_name = match declare(ctx, &mut scope, &rust_path, &name) {
+                Ok(name) => name,
+                Err(reason) => {
+                    writeln!(out, "// {struct_name}: UNSUPPORTED: {reason}").unwrap();
+                    break 'item;
+                }
+            };
+            ctx.type_interfaces.insert(
+                struct_.data.id.clone(),
+                (interface.clone(), struct_name.clone()),
+            );
+            ctx.guest.type_(&rust_path, &struct_name);
+            if let Some(consts) = flags(ctx, &struct_, &rust_path) {
+                let wit = Flags {
+                    name: struct_name,
+                    flags: consts.iter().map(names::ident).collect(),
+                };
+                writeln!(out, "{}", WitTypeKind::Flags(wit.clone()).print()).unwrap();
+                ctx.guest
+                    .definition(&WitType::builtin(WitTypeKind::Flags(wit.clone())));
+                ctx.host.flags_conversion(&rust_path, &wit, &consts);
+                break 'item;
+            }
+            let recursive = is_recursive(&struct_);
+            let wit = 'wit: {
+                // WIT types can't contain themselves, but resources and
+                // indices can
+                if recursive && ctx.config.item(&rust_path).recursive() == RecursiveMode::Arena {
+                    match arena(ctx, &struct_, &struct_name) {
+                        Ok((record, entry, fields)) => {
+                            let entry = WitTypeKind::Record(entry);
+                            writeln!(out, "{}", entry.print()).unwrap();
+                            let WitTypeKind::Record(entry) = entry else {
+                                unreachable!()
+                            };
+                            for (_, ty) in &entry.fields {
+                                local_types(ty, &mut referenced);
                             }
+                            ctx.host.arena_conversion(
+                                &rust_path,
+                                &record,
+                                &entry,
+                                &fields,
+                                struct_.is_non_exhaustive(),
+                            );
+                            break 'wit WitType {
+                                kind: WitTypeKind::Record(record),
+                                functions: (),
+                                source: Source::Local(struct_.data.id.clone()),
+                            };
                         }
+                        Err(reason) => {
+                            writeln!(out, "// {struct_name}: UNSUPPORTED as an arena: {reason}")
+                                .unwrap()
+                        }
+                    }
+                }
+                if recursive {
+                    writeln!(
+                        out,
+                        "// {struct_name}: recursive type, exported as a resource"
+                    )
+                    .unwrap();
+                    break 'wit WitType {
+                        kind: WitTypeKind::Resource(struct_name),
+                        functions: (),
+                        source: Source::Local(struct_.data.id.clone()),
+                    };
+                }
+                // structs that can't be records, e.g. with private or
+                // unsupported fields, are resources
+                let resource = |reason: Option<String>, out: &mut String| {
+                    if let Some(reason) = reason {
+                        writeln!(out, "// {struct_name}.{reason}").unwrap();
                     }
-                    println!("{indent}}}");
                     WitType {
-                        kind: WitTypeKind::Record(Record {
-                            name: struct_name,
-                            fields,
-                        }),
+                        kind: WitTypeKind::Resource(struct_name.clone()),
                         functions: (),
-                        source: if struct_.data.crate_id == root_crate_id {
-                            Source::Local(struct_.data.id.clone())
-                        } else {
-                            Source::Foreign(Some(struct_.data.id.clone()))
-                        },
+                        source: Source::Local(struct_.data.id.clone()),
                     }
-                }
-                StructItemKind::StructUnit(unit) => {
-                    println!("{indent}record {}; // unit struct", struct_name);
-                    todo!()
-                }
-                StructItemKind::StructTuple(tuple) => {
-                    print!("{indent}type {} = tuple<", struct_name);
-
-                    let mut fields = Vec::new();
-                    for (i, field_type) in tuple.fields().enumerate() {
-                        if i > 0 {
-                            print!(", ");
+                };
+                match struct_.struct_kind() {
+                    StructItemKind::StructPlain(plain) => {
+                        if plain.fields_stripped() {
+                            break 'wit resource(None, &mut out);
                         }
-                        print!("TODO");
-                        if let Some(field_type) = field_type {
-                            match field_type.type_kind() {
-                                query::TypeKind::ResolvedPath(path) => {
-                                    if let Some(item) = path.item() {
-                                        if item.data.crate_id == root_crate_id {
-                                            fields.push(WitType {
-                                                kind: todo!("depends on the path"),
-                                                functions: (),
-                                                source: todo!(),
-                                            })
-                                        } else {
-                                            todo!();
-                                        }
-                                    } else if let Some(summary) = path.summary() {
-                                        if summary.crate_id == root_crate_id {
-                                            fields.push(WitType {
-                                                kind: todo!(),
-                                                functions: todo!(),
-                                                source: todo!(),
-                                            });
-                                        }
-                                    } else {
-                                        todo!();
-                                    }
-                                }
-                                query::TypeKind::DynTrait(_) => todo!("query::TypeKind::DynTrait"),
-                                // if it contains a generic, make it a resource
-                                query::TypeKind::Generic(_) => {
-                                    break 'wit WitType {
-                                        kind: WitTypeKind::Resource(struct_name),
-                                        functions: (),
-                                        source: Source::Foreign(None),
-                                    };
-                                }
-                                query::TypeKind::Primitive(primitive) => {
-                                    fields.push(WitType {
-                                        kind: WitTypeKind::from_rust_type(primitive)
-                                            .expect("known primitive"),
-                                        functions: (),
-                                        source: Source::Foreign(None),
-                                    });
-                                }
-                                query::TypeKind::FunctionPointer(_) => {
-                                    todo!("query::TypeKind::FunctionPointer")
+
+                        let mut fields = Vec::with_capacity(plain.fields().count());
+                        let mut field_scope = names::Scope::default();
+                        for (field_name, field_type) in plain.fields() {
+                            let field_name = match field_scope.declare(field_name) {
+                                Ok(name) => name,
+                                // if two fields get the same name, make it a resource
+                                Err(reason) => {
+                                    let reason = format!(
+                                        "{}: UNSUPPORTED: {reason}",
+                                        names::ident(field_name)
+                                    );
+                                    break 'wit resource(Some(reason), &mut out);
                                 }
-                                query::TypeKind::Tuple(_) => todo!("query::TypeKind::Tuple"),
-                                query::TypeKind::Slice(_) => todo!("query::TypeKind::Slice"),
-                                query::TypeKind::Array(_) => todo!("query::TypeKind::Array"),
-                                query::TypeKind::ImplTrait(_) => {
-                                    todo!("query::TypeKind::ImplTrait")
+                            };
+                            // if it contains a generic or a borrow, or can't be
+                            // represented, make it a resource
+                            let ty = match field_type.type_kind() {
+                                query::TypeKind::Generic(_) => Err("generic field".to_string()),
+                                query::TypeKind::BorrowedRef(_) => {
+                                    Err("borrowed field".to_string())
                                 }
-                                query::TypeKind::Infer => todo!("query::TypeKind::Infer"),
-                                query::TypeKind::RawPointer(_) => {
-                                    todo!("query::TypeKind::RawPointer")
+                                _ => wit_type(ctx, &field_type).and_then(not_handle),
+                            };
+                            match ty {
+                                Ok(ty) => fields.push((field_name, ty)),
+                                Err(reason) => {
+                                    let reason = format!("{field_name}: UNSUPPORTED: {reason}");
+                                    break 'wit resource(Some(reason), &mut out);
                                 }
-                                // if it contains a borrow, make it a resource
+                            }
+                        }
+                        if fields.is_empty() {
+                            break 'wit resource(None, &mut out);
+                        }
+                        WitType {
+                            kind: WitTypeKind::Record(Record {
+                                name: struct_name,
+                                fields,
+                            }),
+                            functions: (),
+                            source: if struct_.data.crate_id == root_crate_id {
+                                Source::Local(struct_.data.id.clone())
+                            } else {
+                                Source::Foreign(Some(struct_.data.id.clone()))
+                            },
+                        }
+                    }
+                    StructItemKind::StructUnit(_) => resource(None, &mut out),
+                    StructItemKind::StructTuple(tuple) => {
+                        let mut fields = Vec::new();
+                        for (idx, field_type) in tuple.fields().enumerate() {
+                            // private fields, so it's opaque
+                            let Some(field_type) = field_type else {
+                                break 'wit resource(None, &mut out);
+                            };
+                            let ty = match field_type.type_kind() {
+                                query::TypeKind::Generic(_) => Err("generic field".to_string()),
                                 query::TypeKind::BorrowedRef(_) => {
-                                    break 'wit WitType {
-                                        kind: WitTypeKind::Resource(struct_name),
-                                        functions: (),
-                                        source: todo!(),
-                                    };
+                                    Err("borrowed field".to_string())
                                 }
-                                query::TypeKind::QualifiedPath(_) => {
-                                    todo!("query::TypeKind::QualifiedPath")
+                                _ => wit_type(ctx, &field_type).and_then(not_handle),
+                            };
+                            match ty {
+                                Ok(ty) => fields.push(ty),
+                                Err(reason) => {
+                                    let reason = format!("{idx}: UNSUPPORTED: {reason}");
+                                    break 'wit resource(Some(reason), &mut out);
                                 }
                             }
-                        } else {
-                            break 'wit WitType {
-                                kind: todo!(),
-                                functions: todo!(),
-                                source: todo!(),
-                            };
                         }
+                        if fields.is_empty() {
+                            break 'wit resource(None, &mut out);
+                        }
+                        WitType {
+                            kind: WitTypeKind::TypeAlias(TypeAlias {
+                                name: struct_name,
+                                ty: Box::new(WitType::builtin(WitTypeKind::Tuple(fields))),
+                            }),
+                            functions: (),
+                            source: if struct_.data.crate_id == root_crate_id {
+                                Source::Local(struct_.data.id.clone())
+                            } else {
+                                Source::Foreign(Some(struct_.data.id.clone()))
+                            },
+                        }
+                    }
+                }
+            };
+
+            if let WitTypeKind::Resource(_) = wit.kind {
+                ctx.resources.insert(struct_.data.id.clone());
+            }
+            local_types(&wit, &mut referenced);
+            ctx.guest.definition(&wit);
+
+            // associated constants, as `[static]` getters of resources, or free
+            // functions prefixed with the type name otherwise
+            let type_name = names::ident(&name);
+            let mut statics = Vec::new();
+            // e.g. `const WHITE: Self`
+            ctx.self_type = Some(struct_.data.id.clone());
+            for constant in struct_
+                .inherent_impls()
+                .flat_map(|impl_| impl_.assoc_consts().collect::<Vec<_>>())
+            {
+                match getter(ctx, constant.name(), &constant.type_()) {
+                    Ok(getter) => {
+                        getter
+                            .types()
+                            .for_each(|ty| local_types(ty, &mut referenced));
+                        statics.push((constant, getter));
+                    }
+                    Err(reason) => writeln!(
+                        out,
+                        "// {type_name}.{}: UNSUPPORTED: {reason}",
+                        names::ident(constant.name())
+                    )
+                    .unwrap(),
+                }
+            }
+            if let (WitTypeKind::Record(record), StructItemKind::StructPlain(plain), false) =
+                (&wit.kind, struct_.struct_kind(), recursive)
+            {
+                let fields = plain.fields().collect::<Vec<_>>();
+                ctx.host.record_conversion(
+                    &rust_path,
+                    record,
+                    &fields,
+                    struct_.is_non_exhaustive(),
+                );
+            }
+            // methods and associated functions, only for resources as records
+            // are copied to the guest
+            let (methods, skipped) = match &wit.kind {
+                WitTypeKind::Resource(_) if struct_.generics().params.is_empty() => {
+                    let taken = statics
+                        .iter()
+                        .map(|(_, getter)| getter.name.clone())
+                        .collect::<Vec<_>>();
+                    resource_methods(ctx, &struct_, &rust_path, &taken)
+                }
+                _ => Default::default(),
+            };
+            ctx.self_type = None;
+            for (name, reason) in skipped {
+                writeln!(out, "// {type_name}.{name}: UNSUPPORTED: {reason}").unwrap();
+            }
+            for method in &methods {
+                method
+                    .wit
+                    .types()
+                    .for_each(|ty| local_types(ty, &mut referenced));
+            }
+            match &wit.kind {
+                WitTypeKind::Resource(name) if !statics.is_empty() || !methods.is_empty() => {
+                    let (receivers, functions): (Vec<_>, Vec<_>) = methods
+                        .iter()
+                        .partition(|method| method.function.receiver().is_some());
+                    let resource = Resource {
+                        name: name.clone(),
+                        methods: receivers.iter().map(|method| method.wit.clone()).collect(),
+                        statics: statics
+                            .iter()
+                            .map(|(_, getter)| getter.clone())
+                            .chain(functions.iter().map(|method| method.wit.clone()))
+                            .collect(),
+                    };
+                    writeln!(out, "{}", resource.print()).unwrap();
+                    for (constant, getter) in &statics {
+                        let expr = format!("{rust_path}::{}", constant.name());
+                        ctx.host
+                            .static_getter(name, &expr, &constant.type_(), getter);
+                    }
+                    if !methods.is_empty() {
+                        ctx.host.resource_methods(&rust_path, name, &methods);
+                    }
+                }
+                _ => {
+                    writeln!(out, "{}", wit.kind.print()).unwrap();
+                    for (constant, getter) in statics {
+                        let getter = WitFunction {
+                            name: wit_ident(&format!("{type_name}-{}", getter.name)),
+                            ..getter
+                        };
+                        writeln!(out, "{}", getter.print()).unwrap();
+                        ctx.guest
+                            .associated(&wit.kind.print_ref(), constant.name(), &getter);
+                        let expr = format!("{rust_path}::{}", constant.name());
+                        ctx.host.getter(&expr, &constant.type_(), &getter);
+                    }
+                }
+            }
+            for (helper, wit) in helpers(ctx, &rust_path, &wit, struct_.trait_impls()) {
+                writeln!(out, "{}", wit.print()).unwrap();
+                ctx.host.helper(&rust_path, helper, &wit);
+                ctx.guest.helper(helper, &wit);
+            }
+        }
+        definitions.push(Definition {
+            id: struct_.data.id.clone(),
+            text: out,
+            deps: referenced[start..].to_vec(),
+        });
+    }
+
+    let enums = exported(module, |inner| inner.as_enum());
+    for (name, enum_) in enums {
+        let mut rust_path = path.to_vec();
+        rust_path.push(name.clone());
+        let rust_path = rust_path.join("::");
+        if !is_public_path(ctx, &enum_.data.id, &rust_path) {
+            continue;
+        }
+        let mut out = String::new();
+        let start = referenced.len();
+        'item: {
+            match annotation(ctx, &enum_, &rust_path) {
+                Ok(annotation) => print_annotation(&mut out, annotation.as_deref()),
+                Err(reason) => {
+                    writeln!(out, "// {}: SKIPPED: {reason}", names::ident(name)).unwrap();
+                    break 'item;
+                }
+            }
+            match declare(ctx, &mut scope, &rust_path, &name) {
+                Ok(wit_name) => {
+                    let id = enum_.data.id.clone();
+                    ctx.guest.type_(&rust_path, &wit_name);
+                    ctx.type_interfaces
+                        .insert(id, (interface.clone(), wit_name));
+                }
+                Err(reason) => {
+                    writeln!(out, "// {}: UNSUPPORTED: {reason}", names::ident(name)).unwrap();
+                    break 'item;
+                }
+            }
+
+            if is_error_type(&enum_) {
+                let (wit, arms) = error_variant(&enum_, &name, &rust_path);
+                local_types(&wit, &mut referenced);
+                if let WitTypeKind::Variant(variant) = &wit.kind {
+                    ctx.host.error_conversion(&rust_path, variant, &arms);
+                }
+                ctx.guest.definition(&wit);
+                writeln!(out, "{}", wit.kind.print()).unwrap();
+                for (helper, wit) in helpers(ctx, &rust_path, &wit, enum_.trait_impls()) {
+                    writeln!(out, "{}", wit.print()).unwrap();
+                    ctx.host.helper(&rust_path, helper, &wit);
+                    ctx.guest.helper(helper, &wit);
+                }
+                break 'item;
+            }
+
+            if enum_.variants().count() > 0
+                && enum_
+                    .variants()
+                    .all(|variant| variant.as_plainkind().is_some())
+            {
+                let (wit, arms) = c_like_enum(&enum_, &name, &rust_path);
+                local_types(&wit, &mut referenced);
+                writeln!(out, "{}", wit.kind.print()).unwrap();
+                let WitTypeKind::Enum(wit_enum) = &wit.kind else {
+                    unreachable!()
+                };
+                ctx.host.enum_conversion(&rust_path, wit_enum, &arms);
+                if ctx.config.item(&rust_path).discriminants() {
+                    match discriminant_functions(&enum_, &wit) {
+                        Ok((to, from)) => {
+                            writeln!(out, "{}", to.print()).unwrap();
+                            writeln!(out, "{}", from.print()).unwrap();
+                            ctx.host
+                                .discriminant_functions(&rust_path, &to, &from, &arms);
+                        }
+                        Err(reason) => writeln!(
+                            out,
+                            "// {}-to-discriminant: UNSUPPORTED: {reason}",
+                            names::ident(name)
+                        )
+                        .unwrap(),
                     }
-                    println!(">;");
+                }
+                break 'item;
+            }
 
-                    WitType {
-                        kind: WitTypeKind::Variant(Variant {
-                            name: struct_name,
-                            fields,
-                        }),
-                        functions: (),
-                        source: if struct_.data.crate_id == root_crate_id {
-                            Source::Local(struct_.data.id.clone())
-                        } else {
-                            Source::Foreign(Some(struct_.data.id.clone()))
-                        },
+            match data_variant(ctx, &enum_, &name) {
+                Ok((wit, cases)) => {
+                    local_types(&wit, &mut referenced);
+                    ctx.guest.definition(&wit);
+                    writeln!(out, "{}", wit.kind.print()).unwrap();
+                    if let WitTypeKind::Variant(variant) = &wit.kind {
+                        ctx.host.variant_conversion(&rust_path, variant, &cases);
                     }
                 }
+                Err(reason) => {
+                    // not defined after all
+                    ctx.type_interfaces.remove(&enum_.data.id);
+                    writeln!(out, "// {}: UNSUPPORTED: {reason}", names::ident(name)).unwrap()
+                }
+            }
+        }
+        definitions.push(Definition {
+            id: enum_.data.id.clone(),
+            text: out,
+            deps: referenced[start..].to_vec(),
+        });
+    }
+
+    out.push_str(&print_definitions(ctx, definitions));
+
+    for function in module.functions() {
+        let mut rust_path = path.to_vec();
+        rust_path.push(function.name().to_string());
+        let wit = annotation(ctx, &function, &rust_path.join("::"))
+            .map_err(str::to_string)
+            .and_then(|annotation| {
+                declare(ctx, &mut scope, &rust_path.join("::"), function.name())?;
+                Ok((annotation, wit_function(ctx, &function)?))
+            });
+        match wit {
+            Ok((annotation, wit)) => {
+                print_annotation(&mut out, annotation.as_deref());
+                writeln!(out, "{}", wit.print()).unwrap();
+                register_types(ctx, module.krate(), &wit, &interface, &mut uses);
+                wit.types().for_each(|ty| local_types(ty, &mut referenced));
+                ctx.host.function(&rust_path.join("::"), &function, &wit);
+                ctx.guest.function(&rust_path.join("::"), &wit);
+            }
+            Err(reason) => writeln!(
+                out,
+                "// {}: UNSUPPORTED: {reason}",
+                names::ident(function.name())
+            )
+            .unwrap(),
+        }
+    }
+
+    for (name, constant) in exported(module, |inner| inner.as_constant()) {
+        let mut rust_path = path.to_vec();
+        rust_path.push(name.clone());
+        let rust_path = rust_path.join("::");
+        let wit = annotation(ctx, &constant, &rust_path)
+            .map_err(str::to_string)
+            .and_then(|annotation| {
+                declare(ctx, &mut scope, &rust_path, &name)?;
+                Ok((annotation, getter(ctx, &name, &constant.type_())?))
+            });
+        match wit {
+            Ok((annotation, wit)) => {
+                print_annotation(&mut out, annotation.as_deref());
+                let value = constant
+                    .value()
+                    .or(Some(constant.expr()).filter(|expr| *expr != "_"));
+                if let Some(value) = value.filter(|_| ctx.config.item(&rust_path).constant_values())
+                {
+                    writeln!(out, "/// `{name} = {value}`").unwrap();
+                }
+                writeln!(out, "{}", wit.print()).unwrap();
+                ctx.host.getter(&rust_path, &constant.type_(), &wit);
+                ctx.guest.function(&rust_path, &wit);
+                wit.types().for_each(|ty| local_types(ty, &mut referenced));
             }
+            Err(reason) => {
+                writeln!(out, "// {}: UNSUPPORTED: {reason}", names::ident(name)).unwrap()
+            }
+        }
+    }
+
+    for (name, static_) in exported(module, |inner| inner.as_static()) {
+        let mut rust_path = path.to_vec();
+        rust_path.push(name.clone());
+        let getter = if static_.is_mutable() {
+            Err("mutable static".to_string())
+        } else {
+            annotation(ctx, &static_, &rust_path.join("::"))
+                .map_err(str::to_string)
+                .and_then(|annotation| {
+                    declare(ctx, &mut scope, &rust_path.join("::"), &name)?;
+                    let wit = getter(ctx, &name, &static_.type_())?;
+                    if wit.types().any(has_handle) {
+                        return Err("resource, which can't be moved out of the static".to_string());
+                    }
+                    Ok((annotation, wit))
+                })
         };
-        let text = wit.kind.print();
-        println!("{text}");
+        match getter {
+            Ok((annotation, wit)) => {
+                print_annotation(&mut out, annotation.as_deref());
+                writeln!(out, "{}", wit.print()).unwrap();
+                // statics can't be moved out of
+                let expr = format!("{}.clone()", rust_path.join("::"));
+                ctx.host.getter(&expr, &static_.type_(), &wit);
+                ctx.guest.function(&rust_path.join("::"), &wit);
+                wit.types().for_each(|ty| local_types(ty, &mut referenced));
+            }
+            Err(reason) => {
+                writeln!(out, "// {}: UNSUPPORTED: {reason}", names::ident(name)).unwrap()
+            }
+        }
     }
 
-    for enum_ in module.enums() {
-        println!("{indent}variant {} {{", enum_.name().to_case(Case::Kebab));
-        println!("{indent}    TODO");
-        // where is Error?
-        println!("{indent}}}");
+    // traits are interfaces of their own, next to the one of the module
+    let mut traits = Vec::new();
+    for (name, trait_) in exported(module, |inner| inner.as_trait()) {
+        let mut rust_path = path.to_vec();
+        rust_path.push(name.clone());
+        let trait_name = interface_name(&rust_path);
+        let interface = annotation(ctx, &trait_, &rust_path.join("::"))
+            .map_err(str::to_string)
+            .and_then(|annotation| Ok((annotation, trait_interface(ctx, &trait_, &trait_name)?)));
+        match interface {
+            Ok((annotation, (interface, methods))) => {
+                let mut body = String::new();
+                let mut referenced = Vec::new();
+                for function in &interface.functions {
+                    writeln!(body, "{}", function.print()).unwrap();
+                    function
+                        .types()
+                        .for_each(|ty| local_types(ty, &mut referenced));
+                }
+                ctx.trait_interfaces.push(interface.name.clone());
+                traits.push(ModuleInterface {
+                    name: interface.name.clone(),
+                    annotation: annotation.or_else(|| gates.clone()),
+                    body,
+                    referenced,
+                    uses: Vec::new(),
+                });
+                ctx.host
+                    .trait_adapter(&ctx.paths, &rust_path.join("::"), &interface, &methods);
+            }
+            Err(reason) => {
+                writeln!(out, "// interface {trait_name}: UNSUPPORTED: {reason}").unwrap()
+            }
+        }
     }
 
+    let mut modules = Vec::new();
     for module in module.modules() {
-        println!("{indent}(module) {}", module.name().to_case(Case::Kebab));
-        process_module(root_crate_id, module, depth + 1);
+        let mut path = path.to_vec();
+        path.push(module.name().to_string());
+        match annotation(ctx, &module, &path.join("::")) {
+            Ok(gates) => modules.push((module, path, gates)),
+            Err(reason) => {
+                writeln!(out, "// {}: SKIPPED: {reason}", interface_name(&path)).unwrap()
+            }
+        }
+    }
+
+    ctx.interfaces.push(ModuleInterface {
+        name: interface,
+        annotation: gates,
+        body: out,
+        referenced,
+        uses,
+    });
+    ctx.interfaces.extend(traits);
+
+    for (module, path, gates) in modules {
+        process_module(ctx, &module, &path, gates);
     }
 }
 
-struct WitFunction {
-    name: String,
-    params: Vec<(String, WitType)>,
-    output: Option<WitType>,
+/// The interface defining the first of the local types `ids` that has one,
+/// the one of the root module if none does
+fn owner_interface(ctx: &Context, ids: &[Id]) -> String {
+    match ids.iter().find_map(|id| ctx.type_interfaces.get(id)) {
+        Some((interface, _)) => interface.clone(),
+        None => ctx.interfaces[0].name.clone(),
+    }
 }
 
-impl WitFunction {
-    fn print(&self) -> impl std::fmt::Display {
-        let mut f = String::new();
-        write!(f, "{name}: func(", name = self.name).unwrap();
-        for (idx, (name, param)) in self.params.iter().enumerate() {
-            if idx > 0 {
-                write!(f, ", ").unwrap();
+/// Add the definitions `text` to the end of `interface`, which then refers
+/// to the local types of `types`
+fn extend_interface<'a>(
+    ctx: &mut Context,
+    interface: &str,
+    text: &str,
+    types: impl IntoIterator<Item = &'a WitType>,
+) {
+    let interface = ctx
+        .interfaces
+        .iter_mut()
+        .find(|other| other.name == interface)
+        .expect("items go in an interface that was walked");
+    writeln!(interface.body, "{text}").unwrap();
+    for ty in types {
+        local_types(ty, &mut interface.referenced);
+    }
+}
+
+/// The interface of the module at `path`, named after the path within the
+/// crate, e.g. `experimental-scene` for `macroquad::experimental::scene`, or
+/// `{crate}-root` for the root module, as the combined world is `{crate}`
+fn interface_name(path: &[String]) -> String {
+    match path {
+        [krate] => names::ident(format!("{krate}_root")),
+        [_, path @ ..] => names::ident(path.join("_")),
+        [] => unreachable!("modules are in a crate"),
+    }
+}
+
+/// The interfaces of the modules, each `use`-ing the types it refers to from
+/// other interfaces, and after the interfaces it uses
+///
+/// Rust modules can use each other's types, but WIT interfaces can't `use`
+/// each other in a cycle, so those interfaces are reported, and printed last.
+fn print_interfaces(ctx: &Context) -> String {
+    // the types each interface uses, by the interface they are defined in
+    let mut uses: BTreeMap<&str, BTreeMap<&str, BTreeSet<&str>>> = BTreeMap::new();
+    for interface in &ctx.interfaces {
+        let uses = uses.entry(&interface.name).or_default();
+        for id in &interface.referenced {
+            match ctx.type_interfaces.get(id) {
+                Some((other, ty)) if *other != interface.name => {
+                    uses.entry(other).or_default().insert(ty);
+                }
+                _ => {}
             }
-            write!(f, "{name}: {param}", param = param.kind.print()).unwrap();
         }
-        write!(f, ")").unwrap();
-        match &self.output {
-            Some(output) => write!(f, " -> {output};", output = output.kind.print()).unwrap(),
-            None => write!(f, ";").unwrap(),
+        for (other, ty) in &interface.uses {
+            if *other != interface.name {
+                uses.entry(other).or_default().insert(ty);
+            }
         }
-        f
     }
+    let deps = uses
+        .iter()
+        .map(|(name, used)| (*name, used.keys().copied().collect()))
+        .collect();
+    let (order, left) = topological_order(&deps);
+
+    let mut f = String::new();
+    // interfaces using the ones in a cycle aren't in one themselves
+    let cyclic = left
+        .iter()
+        .copied()
+        .filter(|name| in_cycle(&deps, *name))
+        .collect::<Vec<_>>();
+    if !cyclic.is_empty() {
+        writeln!(
+            f,
+            "// interfaces {}: UNSUPPORTED: cyclic `use`s, as their Rust modules refer to each other's types",
+            cyclic.join(", ")
+        )
+        .unwrap();
+    }
+    for name in order.into_iter().chain(left) {
+        let interface = ctx
+            .interfaces
+            .iter()
+            .find(|interface| interface.name == name)
+            .expect("uses are keyed by interface");
+        print_annotation(&mut f, interface.annotation.as_deref());
+        writeln!(f, "interface {name} {{").unwrap();
+        for (other, types) in &uses[name] {
+            let types = types.iter().copied().collect::<Vec<_>>().join(", ");
+            writeln!(f, "    use {other}.{{{types}}};").unwrap();
+        }
+        if !interface.body.is_empty() {
+            writeln!(f, "{}", indented(&interface.body, "    ")).unwrap();
+        }
+        writeln!(f, "}}").unwrap();
+    }
+    f
 }
 
-struct WitType {
-    kind: WitTypeKind,
-    functions: (),
-    source: Source,
+/// The keys of `deps` in an order where each comes after the keys it depends
+/// on, taking the first one that is ready each time, and the keys left over
+/// because they depend on a cycle
+fn topological_order<K: Ord + Copy>(deps: &BTreeMap<K, BTreeSet<K>>) -> (Vec<K>, Vec<K>) {
+    let mut order = Vec::new();
+    while let Some(key) = deps.iter().find_map(|(key, key_deps)| {
+        let ready = !order.contains(key)
+            && key_deps
+                .iter()
+                .all(|dep| order.contains(dep) || !deps.contains_key(dep));
+        ready.then_some(*key)
+    }) {
+        order.push(key);
+    }
+    let left = deps
+        .keys()
+        .filter(|key| !order.contains(key))
+        .copied()
+        .collect();
+    (order, left)
 }
 
-enum Source {
-    Local(Id),
-    Foreign(Option<Id>),
+/// Whether `key` depends on itself through `deps`
+fn in_cycle<K: Ord + Copy>(deps: &BTreeMap<K, BTreeSet<K>>, key: K) -> bool {
+    let mut seen = BTreeSet::new();
+    let mut stack = deps
+        .get(&key)
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect::<Vec<_>>();
+    while let Some(next) = stack.pop() {
+        if next == key {
+            return true;
+        }
+        if seen.insert(next) {
+            stack.extend(deps.get(&next).into_iter().flatten().copied());
+        }
+    }
+    false
 }
 
-enum WitTypeKind {
-    U8,
-    U16,
-    U32,
-    U64,
-    S8,
-    S16,
+/// A type definition of a module, with what was printed for it
+struct Definition {
+    id: Id,
+    text: String,
+    /// Local types the definition refers to, see [`local_types`]
+    deps: Vec<Id>,
+}
+
+/// The type definitions of a module, each after the types it refers to
+///
+/// WIT types can't refer to each other in a cycle, so those are reported. A
+/// struct referring to itself is already a resource, see [`is_recursive`].
+fn print_definitions(ctx: &Context, definitions: Vec<Definition>) -> String {
+    let index = definitions
+        .iter()
+        .enumerate()
+        .map(|(idx, definition)| (&definition.id, idx))
+        .collect::<HashMap<_, _>>();
+    let deps = definitions
+        .iter()
+        .enumerate()
+        .map(|(idx, definition)| {
+            let deps = definition
+                .deps
+                .iter()
+                .filter_map(|id| index.get(id).copied())
+                .filter(|dep| *dep != idx)
+                .collect();
+            (idx, deps)
+        })
+        .collect::<BTreeMap<usize, BTreeSet<usize>>>();
+    let (order, left) = topological_order(&deps);
+
+    let mut f = String::new();
+    let recursive = left
+        .iter()
+        .filter(|idx| in_cycle(&deps, **idx))
+        .filter_map(|idx| ctx.type_interfaces.get(&definitions[*idx].id))
+        .map(|(_, name)| name.as_str())
+        .collect::<Vec<_>>();
+    if !recursive.is_empty() {
+        writeln!(
+            f,
+            "// {}: UNSUPPORTED: recursive types, which WIT can't define",
+            recursive.join(", ")
+        )
+        .unwrap();
+    }
+    for idx in order.into_iter().chain(left) {
+        f.push_str(&definitions[idx].text);
+    }
+    f
+}
+
+/// Whether a field of `struct_` refers to `struct_` itself, e.g. through a
+/// `Vec<Node>` or `Option<Box<Node>>`
+fn is_recursive(struct_: &query::Item<&Struct>) -> bool {
+    let id = &struct_.data.id;
+    match struct_.struct_kind() {
+        StructItemKind::StructPlain(plain) => plain.fields().any(|(_, ty)| ty.mentions(id)),
+        StructItemKind::StructTuple(tuple) => tuple.fields().flatten().any(|ty| ty.mentions(id)),
+        StructItemKind::StructUnit(_) => false,
+    }
+}
+
+/// The recursive structs exported as arenas, which types containing them
+/// have to know about before they are walked to convert back with `TryFrom`
+fn arenas(ctx: &Context, krate: &query::CrateQuery) -> HashSet<Id> {
+    krate
+        .all_structs()
+        .filter(|struct_| struct_.data.crate_id == ctx.root_crate_id && is_recursive(struct_))
+        .filter(|struct_| {
+            ctx.paths.get(&struct_.data.id).is_some_and(|path| {
+                ctx.config.item(&path.join("::")).recursive() == RecursiveMode::Arena
+            })
+        })
+        .filter(|struct_| arena(ctx, struct_, &names::ident(struct_.name())).is_ok())
+        .map(|struct_| struct_.data.id.clone())
+        .collect()
+}
+
+/// How a field of a struct exported as an arena refers to the struct itself,
+/// see [`arena`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SelfRef {
+    /// `Box<Node>`, as `u32`
+    Box,
+    /// `Option<Box<Node>>`, as `option<u32>`
+    OptionBox,
+    /// `Vec<Node>`, or `Vec<Box<Node>>` if `boxed`, as `list<u32>`
+    Vec { boxed: bool },
+}
+
+/// How `ty` refers to the type `id`, `None` if it doesn't, or `Some(None)` if
+/// it does in a way an index can't stand for
+fn self_ref(ty: &query::Item<&Type>, id: &Id) -> Option<Option<SelfRef>> {
+    if !ty.mentions(id) {
+        return None;
+    }
+    let is = |ty: &query::Item<&Type>, path: &[&str]| matches!(ty.type_kind(), query::TypeKind::ResolvedPath(resolved) if resolved.is(path));
+    let is_self = |ty: &query::Item<&Type>| matches!(ty.type_kind(), query::TypeKind::ResolvedPath(resolved) if resolved.id() == id);
+    let is_box =
+        |ty: &query::Item<&Type>| is(ty, BOX_PATH) && ty.type_args().any(|arg| is_self(&arg));
+    let arg = ty.type_args().next();
+    let self_ref = if is_box(ty) {
+        SelfRef::Box
+    } else if is(ty, OPTION_PATH) && arg.as_ref().is_some_and(is_box) {
+        SelfRef::OptionBox
+    } else if is(ty, VEC_PATH) && arg.as_ref().is_some_and(is_self) {
+        SelfRef::Vec { boxed: false }
+    } else if is(ty, VEC_PATH) && arg.as_ref().is_some_and(is_box) {
+        SelfRef::Vec { boxed: true }
+    } else {
+        return Some(None);
+    };
+    Some(Some(self_ref))
+}
+
+/// A field of a struct exported as an arena, with how it refers to the
+/// struct, if it does
+type ArenaField<'c> = (&'c str, query::Item<'c, &'c Type>, Option<SelfRef>);
+
+/// The record `struct_` is exported as when it contains itself, the record of
+/// its entries, and its fields
+///
+/// The record is a list of entries, e.g.
+/// `record node { entries: list<node-entry> }`, where the fields referring to
+/// the struct are indices of entries, e.g. `children: list<u32>` for a
+/// `Vec<Node>`. Entries only refer to earlier ones, and the root is the last.
+fn arena<'c>(
+    ctx: &Context,
+    struct_: &query::Item<'c, &'c Struct>,
+    name: &str,
+) -> Result<(Record, Record, Vec<ArenaField<'c>>), String> {
+    let StructItemKind::StructPlain(plain) = struct_.struct_kind() else {
+        return Err("not a struct with named fields".to_string());
+    };
+    if plain.fields_stripped() {
+        return Err("private fields".to_string());
+    }
+    let index = || WitType::builtin(WitTypeKind::U32);
+    let mut fields = Vec::new();
+    let mut entry_fields = Vec::new();
+    for (field_name, ty) in plain.fields() {
+        let (wit, self_ref) = match self_ref(&ty, &struct_.data.id) {
+            None => {
+                let wit = wit_type(ctx, &ty)
+                    .and_then(not_handle)
+                    .map_err(|reason| format!("field `{field_name}`: {reason}"))?;
+                (wit, None)
+            }
+            Some(Some(self_ref)) => {
+                let kind = match self_ref {
+                    SelfRef::Box => WitTypeKind::U32,
+                    SelfRef::OptionBox => WitTypeKind::Option(Box::new(index())),
+                    SelfRef::Vec { .. } => WitTypeKind::List(Box::new(index())),
+                };
+                (WitType::builtin(kind), Some(self_ref))
+            }
+            Some(None) => {
+                return Err(format!(
+                    "field `{field_name}` contains `{}` other than through a `Box`, `Option<Box<_>>` or `Vec`",
+                    struct_.name()
+                ))
+            }
+        };
+        entry_fields.push((names::ident(field_name), wit));
+        fields.push((field_name, ty, self_ref));
+    }
+    let entry = Record {
+        name: wit_ident(&format!("{name}-entry")),
+        fields: entry_fields,
+    };
+    let entries = WitType::builtin(WitTypeKind::Named(entry.name.clone()));
+    let record = Record {
+        name: name.to_string(),
+        fields: vec![(
+            "entries".to_string(),
+            WitType::builtin(WitTypeKind::List(Box::new(entries))),
+        )],
+    };
+    Ok((record, entry, fields))
+}
+
+/// The local types `ty` refers to, see [`Source::Local`]
+fn local_types(ty: &WitType, ids: &mut Vec<Id>) {
+    ty.visit(&mut |ty| {
+        if let Source::Local(id) = &ty.source {
+            ids.push(id.clone());
+        }
+    });
+}
+
+/// Remember the iterators and trait objects `wit` uses, as they are defined
+/// once the whole crate has been walked
+///
+/// `wit` is in `interface`, and `uses` gets the iterator resources defined in
+/// other interfaces.
+fn register_types(
+    ctx: &mut Context,
+    krate: &Crate,
+    wit: &WitFunction,
+    interface: &str,
+    uses: &mut Vec<(String, String)>,
+) {
+    for ty in wit.types() {
+        ty.visit(&mut |ty| {
+            if let WitTypeKind::Iterator(item) = &ty.kind {
+                let name = ty.kind.print_ref();
+                let (owner, _) = ctx
+                    .iterators
+                    .entry(name.clone())
+                    .or_insert_with(|| (interface.to_string(), (**item).clone()));
+                if owner != interface {
+                    uses.push((owner.clone(), name));
+                }
+            }
+            if let (WitTypeKind::Resource(name) | WitTypeKind::Borrow(name), Source::Local(id)) =
+                (&ty.kind, &ty.source)
+            {
+                let is_trait = krate
+                    .index
+                    .get(id)
+                    .is_some_and(|item| item.inner.as_trait().is_some());
+                if is_trait {
+                    ctx.trait_objects
+                        .entry(name.clone())
+                        .or_insert_with(|| (interface.to_string(), id.clone()));
+                }
+            }
+        });
+    }
+}
+
+/// Indent every line of `text`, leaving empty lines empty
+fn indented(text: &str, indent: &str) -> String {
+    text.lines()
+        .map(|line| match line {
+            "" => String::new(),
+            line => format!("{indent}{line}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The doc note and feature gates to print above an exported item, or the
+/// reason the item isn't exported
+///
+/// - `#[deprecated]` items get a doc note and maybe `@deprecated`, which WIT
+///   only allows after a gate, see [`config::DeprecatedMode`]
+/// - items behind a cargo feature, as told by `#[doc(cfg(feature = "x"))]`,
+///   are `@unstable(feature = x)`
+/// - other items are `@since` the version of the `since` setting
+fn annotation<K>(
+    ctx: &Context,
+    item: &query::Item<K>,
+    rust_path: &str,
+) -> Result<Option<String>, &'static str> {
+    if *item.visibility() != Visibility::Public {
+        return Err("not public");
+    }
+    if item.is_doc_hidden() {
+        return Err("doc(hidden)");
+    }
+    let config = ctx.config.item(rust_path);
+    let mut lines = Vec::new();
+    let mut deprecated = None;
+    if let Some(deprecation) = item.deprecation() {
+        let mut note = match &deprecation.since {
+            Some(since) => format!("/// Deprecated since {since}"),
+            None => "/// Deprecated".to_string(),
+        };
+        if let Some(reason) = &deprecation.note {
+            write!(note, ": {reason}").unwrap();
+        }
+        lines.push(note);
+        match config.deprecated() {
+            config::DeprecatedMode::Skip => return Err("deprecated"),
+            config::DeprecatedMode::Annotate => {
+                deprecated = deprecation
+                    .since
+                    .as_deref()
+                    .filter(|since| is_semver(since))
+                    .map(|version| format!("@deprecated(version = {version})"));
+            }
+            config::DeprecatedMode::Note => {}
+        }
+    }
+    let since = match config.since.as_deref() {
+        Some("crate") => item.krate().crate_version.as_deref(),
+        since => since,
+    };
+    let gate = match (
+        item.cfg_features().first(),
+        since.filter(|since| is_semver(since)),
+    ) {
+        (Some(feature), _) => Some(format!("@unstable(feature = {})", wit_ident(feature))),
+        (None, Some(version)) => Some(format!("@since(version = {version})")),
+        (None, None) => None,
+    };
+    // `@deprecated` only goes with a gate, otherwise the note has to do
+    if let Some(gate) = gate {
+        lines.push(gate);
+        lines.extend(deprecated);
+    }
+    Ok((!lines.is_empty()).then(|| lines.join("\n")))
+}
+
+/// WIT versions are semver, e.g. `0.4.4`
+fn is_semver(version: &str) -> bool {
+    let parts = version.split('.').collect::<Vec<_>>();
+    parts.len() == 3 && parts.iter().all(|part| part.parse::<u64>().is_ok())
+}
+
+/// Write the lines of an [`annotation`] above its item
+fn print_annotation(f: &mut String, annotation: Option<&str>) {
+    if let Some(annotation) = annotation {
+        writeln!(f, "{annotation}").unwrap();
+    }
+}
+
+/// Items of `module` selected by `kind`, both those defined in it and those
+/// re-exported with a (non-glob) `use`, with the name they are exported as
+fn exported<'c, T>(
+    module: &query::Item<'c, &'c rustdoc_types::Module>,
+    kind: impl Fn(&'c ItemEnum) -> Option<T>,
+) -> Vec<(String, query::Item<'c, T>)> {
+    let krate = module.krate();
+    let defined = module.raw_items().filter_map(|(_, item)| {
+        let inner = kind(&item.inner)?;
+        Some((item.name.clone()?, query::Item::new(krate, item, inner)))
+    });
+    let reexported = module.raw_items().filter_map(|(_, item)| {
+        let import = item.inner.as_import().filter(|import| !import.glob)?;
+        let target = krate.index.get(import.id.as_ref()?)?;
+        let inner = kind(&target.inner)?;
+        Some((import.name.clone(), query::Item::new(krate, target, inner)))
+    });
+    defined.chain(reexported).collect()
+}
+
+/// The public Rust path of every item reachable from `module`, e.g.
+/// `macroquad::Error` for an enum defined in a private module and re-exported
+/// from the crate root
+fn public_paths(
+    module: &query::Item<&rustdoc_types::Module>,
+    path: &[String],
+    paths: &mut HashMap<Id, Vec<String>>,
+) {
+    let summaries = &module.krate().paths;
+    let mut insert = |id: &Id, name: &str| {
+        let mut item_path = path.to_vec();
+        item_path.push(name.to_string());
+        // the shortest path, or the one rustdoc gives for the item on a tie
+        let is_summary = summaries
+            .get(id)
+            .is_some_and(|summary| summary.path == item_path);
+        match paths.get(id) {
+            Some(existing) if existing.len() < item_path.len() => {}
+            Some(existing) if existing.len() == item_path.len() && !is_summary => {}
+            _ => {
+                paths.insert(id.clone(), item_path);
+            }
+        }
+    };
+    for (id, item) in module.raw_items() {
+        match &item.inner {
+            ItemEnum::Import(import) if !import.glob => {
+                if let Some(target) = &import.id {
+                    insert(target, &import.name);
+                }
+            }
+            ItemEnum::Module(_) => {}
+            _ => {
+                if let Some(name) = &item.name {
+                    insert(id, name);
+                }
+            }
+        }
+    }
+    for submodule in module.modules() {
+        let mut path = path.to_vec();
+        path.push(submodule.name().to_string());
+        public_paths(&submodule, &path, paths);
+    }
+}
+
+/// Whether `rust_path` is the public path of the type `id`, where it is
+/// defined, rather than one of the paths it is re-exported at, e.g. `Color`
+/// is defined in `color` rather than `prelude`
+fn is_public_path(ctx: &Context, id: &Id, rust_path: &str) -> bool {
+    ctx.paths
+        .get(id)
+        .is_none_or(|path| path.join("::") == rust_path)
+}
+
+/// Error types implement `std::error::Error`, or are named like an error and
+/// implement `Display`
+fn is_error_type(enum_: &query::Item<&rustdoc_types::Enum>) -> bool {
+    enum_.trait_impls().any(|impl_| {
+        impl_.trait_().is_some_and(|trait_| {
+            trait_.is(ERROR_PATH) || (trait_.is(DISPLAY_PATH) && enum_.name().ends_with("Error"))
+        })
+    })
+}
+
+/// A variant with a case per Rust variant, where cases for variants with fields
+/// carry the error's `Display` text (wrapped errors are usually foreign types
+/// that can't be represented), and a fallback `message` case for anything else
+///
+/// Also returns the Rust pattern for each case, for the host conversion.
+fn error_variant(
+    enum_: &query::Item<&rustdoc_types::Enum>,
+    name: &str,
+    rust_path: &str,
+) -> (WitType, Vec<(String, String)>) {
+    let mut cases = Vec::new();
+    let mut arms = Vec::new();
+    for variant in enum_.variants() {
+        let case = names::ident(variant.name());
+        let (pattern, payload) = match variant.variantkind() {
+            query::VariantItemKind::Plain(_) => (format!("{rust_path}::{}", variant.name()), None),
+            query::VariantItemKind::Tuple(_) => (
+                format!("{rust_path}::{}(..)", variant.name()),
+                Some(WitType::builtin(WitTypeKind::String)),
+            ),
+            query::VariantItemKind::Struct(_) => (
+                format!("{rust_path}::{} {{ .. }}", variant.name()),
+                Some(WitType::builtin(WitTypeKind::String)),
+            ),
+        };
+        arms.push((pattern, case.clone()));
+        cases.push((case, payload));
+    }
+    let fallback = if cases.iter().any(|(case, _)| case == "message") {
+        "error-message"
+    } else {
+        "message"
+    };
+    cases.push((
+        fallback.to_string(),
+        Some(WitType::builtin(WitTypeKind::String)),
+    ));
+
+    let wit = WitType {
+        kind: WitTypeKind::Variant(Variant {
+            name: names::ident(name),
+            cases,
+        }),
+        functions: (),
+        source: Source::Local(enum_.data.id.clone()),
+    };
+    (wit, arms)
+}
+
+/// A WIT `enum` for an enum without payloads, with the explicit discriminants
+/// documented on the cases, and the Rust pattern of each case
+///
+/// `#[non_exhaustive]` enums get an `unknown` case, with `_` as its pattern.
+fn c_like_enum(
+    enum_: &query::Item<&rustdoc_types::Enum>,
+    name: &str,
+    rust_path: &str,
+) -> (WitType, Vec<(String, String)>) {
+    let mut cases = Vec::new();
+    let mut arms = Vec::new();
+    for variant in enum_.variants() {
+        let case = names::ident(variant.name());
+        let doc = variant
+            .discriminant()
+            .map(|discriminant| format!("`{} = {}`", variant.name(), discriminant.value));
+        arms.push((format!("{rust_path}::{}", variant.name()), case.clone()));
+        cases.push((case, doc));
+    }
+    // for variants added in later versions of the library
+    if enum_.is_non_exhaustive() {
+        let fallback = if cases.iter().any(|(case, _)| case == "unknown") {
+            "other"
+        } else {
+            "unknown"
+        };
+        arms.push(("_".to_string(), fallback.to_string()));
+        cases.push((fallback.to_string(), None));
+    }
+    let wit = WitType {
+        kind: WitTypeKind::Enum(Enum {
+            name: names::ident(name),
+            cases,
+        }),
+        functions: (),
+        source: Source::Local(enum_.data.id.clone()),
+    };
+    (wit, arms)
+}
+
+/// A case of a data enum and the fields of its variant, see [`data_variant`]
+struct DataCase<'c> {
+    /// The Rust name of the variant
+    variant: String,
+    case: String,
+    /// The fields, named for struct variants
+    fields: Vec<(Option<&'c str>, query::Item<'c, &'c rustdoc_types::Type>)>,
+}
+
+/// A WIT `variant` for an enum with payloads, and its cases, or the reason it
+/// can't be one
+///
+/// A variant with one field has it as the payload, and one with several has a
+/// tuple of them, e.g. `dragging(tuple<vec2, option<u64>>)` for
+/// `Dragging(Vec2, Option<Id>)`. `#[non_exhaustive]` enums get an `unknown`
+/// case, like in [`c_like_enum`].
+fn data_variant<'c>(
+    ctx: &Context,
+    enum_: &query::Item<'c, &'c rustdoc_types::Enum>,
+    name: &str,
+) -> Result<(WitType, Vec<DataCase<'c>>), String> {
+    let is_generic = enum_.generics().params.iter().any(|param| {
+        !matches!(
+            param.kind,
+            rustdoc_types::GenericParamDefKind::Lifetime { .. }
+        )
+    });
+    if is_generic {
+        return Err("generic enum".to_string());
+    }
+    let mut cases = Vec::new();
+    let mut data_cases = Vec::new();
+    for variant in enum_.variants() {
+        let fields = match variant.variantkind() {
+            query::VariantItemKind::Plain(_) => Vec::new(),
+            query::VariantItemKind::Tuple(tuple) => tuple
+                .fields()
+                .map(|field| {
+                    let ty = field.ok_or(format!("`{}` has private fields", variant.name()))?;
+                    Ok((None, ty))
+                })
+                .collect::<Result<Vec<_>, String>>()?,
+            query::VariantItemKind::Struct(struct_) if struct_.fields_stripped() => {
+                return Err(format!("`{}` has private fields", variant.name()))
+            }
+            query::VariantItemKind::Struct(struct_) => struct_
+                .fields()
+                .map(|(name, ty)| (Some(name), ty))
+                .collect(),
+        };
+        let mut types = Vec::new();
+        for (_, ty) in &fields {
+            if let query::TypeKind::BorrowedRef(_) = ty.type_kind() {
+                return Err(format!("`{}` has a borrowed field", variant.name()));
+            }
+            // WIT types can't contain themselves, and only structs are
+            // exported as arenas
+            if ty.mentions(&enum_.data.id) {
+                return Err(format!("`{}` contains the enum itself", variant.name()));
+            }
+            types.push(
+                wit_type(ctx, ty)
+                    .and_then(not_handle)
+                    .map_err(|reason| format!("`{}`: {reason}", variant.name()))?,
+            );
+        }
+        let payload = match types.len() {
+            0 => None,
+            1 => types.pop(),
+            _ => Some(WitType::builtin(WitTypeKind::Tuple(types))),
+        };
+        let case = names::ident(variant.name());
+        cases.push((case.clone(), payload));
+        data_cases.push(DataCase {
+            variant: variant.name().to_string(),
+            case,
+            fields,
+        });
+    }
+    // for variants added in later versions of the library
+    if enum_.is_non_exhaustive() {
+        let fallback = if cases.iter().any(|(case, _)| case == "unknown") {
+            "other"
+        } else {
+            "unknown"
+        };
+        cases.push((fallback.to_string(), None));
+    }
+    let wit = WitType {
+        kind: WitTypeKind::Variant(Variant {
+            name: names::ident(name),
+            cases,
+        }),
+        functions: (),
+        source: Source::Local(enum_.data.id.clone()),
+    };
+    Ok((wit, data_cases))
+}
+
+/// `to-discriminant` and `from-discriminant` functions for integer based
+/// protocols, in the `#[repr]` type of the enum or `s64` by default, e.g.
+/// `key-code-to-discriminant: func(value: key-code) -> u32` and
+/// `key-code-from-discriminant: func(discriminant: u32) -> option<key-code>`
+fn discriminant_functions(
+    enum_: &query::Item<&rustdoc_types::Enum>,
+    wit: &WitType,
+) -> Result<(WitFunction, WitFunction), String> {
+    let int = match enum_.repr() {
+        Some(repr @ ("u128" | "i128")) => {
+            return Err(format!("`#[repr({repr})]`, which has no WIT integer type"))
+        }
+        Some("u8") => WitTypeKind::U8,
+        Some("u16") => WitTypeKind::U16,
+        Some("u32") => WitTypeKind::U32,
+        Some("u64" | "usize") => WitTypeKind::U64,
+        Some("i8") => WitTypeKind::S8,
+        Some("i16") => WitTypeKind::S16,
+        Some("i32") => WitTypeKind::S32,
+        _ => WitTypeKind::S64,
+    };
+    let int = WitType::builtin(int);
+    let name = wit.kind.print_ref();
+    let to = WitFunction {
+        name: wit_ident(&format!("{name}-to-discriminant")),
+        params: vec![("value".to_string(), wit.clone())],
+        output: Some(int.clone()),
+        is_async: false,
+    };
+    let from = WitFunction {
+        name: wit_ident(&format!("{name}-from-discriminant")),
+        params: vec![("discriminant".to_string(), int)],
+        output: Some(WitType::builtin(WitTypeKind::Option(Box::new(wit.clone())))),
+        is_async: false,
+    };
+    Ok((to, from))
+}
+
+/// A free function, or the reason it can't be exported
+fn wit_function(
+    ctx: &Context,
+    function: &query::Item<&rustdoc_types::Function>,
+) -> Result<WitFunction, String> {
+    // type parameters are only supported as closures, e.g. `F: FnMut(f32)`
+    let is_generic = function
+        .generics()
+        .params
+        .iter()
+        .any(|param| match param.kind {
+            rustdoc_types::GenericParamDefKind::Lifetime { .. } => false,
+            rustdoc_types::GenericParamDefKind::Type {
+                synthetic: true, ..
+            } => false,
+            _ => fn_signature(function, &param.name).is_none(),
+        });
+    if is_generic {
+        return Err("generic function".to_string());
+    }
+    let future_output = future_output(function);
+    let is_async = function.header().async_ || future_output.is_some();
+    if is_async && !ctx.async_ {
+        return Err("async function (export with `--async`)".to_string());
+    }
+
+    let mut params = Vec::new();
+    for (idx, (name, ty)) in function.params().enumerate() {
+        if let Some(record) = non_exhaustive_record(&ty) {
+            return Err(format!(
+                "parameter `{name}`: `{record}` is `#[non_exhaustive]`, so it can't be built from WIT"
+            ));
+        }
+        // the host can't call back into the guest while the guest is calling it
+        if closure_signature(function, &ty).is_some() {
+            return Err(format!(
+                "parameter `{name}`: closure, which the host can't call during a guest call"
+            ));
+        }
+        let ty = wit_type(ctx, &ty).map_err(|reason| format!("parameter `{name}`: {reason}"))?;
+        params.push((param_name(name, idx), ty));
+    }
+    if let Some(query::TypeKind::BorrowedRef(borrowed)) = function.output().map(|ty| ty.type_kind())
+    {
+        if let query::TypeKind::DynTrait(_) = borrowed.type_().type_kind() {
+            return Err("return type: borrowed trait object".to_string());
+        }
+    }
+    // trait objects live in the host's resource table, which needs `Send`
+    if function
+        .output()
+        .is_some_and(|output| has_unsend_trait_object(&output))
+    {
+        return Err("return type: trait object that isn't `Send`".to_string());
+    }
+    let rust_output = future_output.or_else(|| function.output());
+    let mut output = rust_output
+        .as_ref()
+        .map(|ty| wit_type(ctx, ty))
+        .transpose()
+        .map_err(|reason| format!("return type: {reason}"))?
+        .filter(|ty| !matches!(&ty.kind, WitTypeKind::Tuple(types) if types.is_empty()));
+
+    // resources go through the host's resource table, see [`handle_position`]
+    let mut borrows = Vec::new();
+    if let Some(query::TypeKind::BorrowedRef(borrowed)) =
+        function.receiver().map(|ty| ty.type_kind())
+    {
+        borrows.push(borrowed.is_mutable());
+    }
+    for ((name, rust), (_, ty)) in function.params().zip(&params) {
+        handle_position(&rust, ty, false)
+            .map_err(|reason| format!("parameter `{name}`: {reason}"))?;
+        let borrowed = match &ty.kind {
+            WitTypeKind::Option(inner) => matches!(inner.kind, WitTypeKind::Borrow(_)),
+            kind => matches!(kind, WitTypeKind::Borrow(_)),
+        };
+        if borrowed {
+            // `&mut T`, or `Option<&mut T>`
+            let is_mutable = |ty: &query::Item<&Type>| matches!(ty.type_kind(), query::TypeKind::BorrowedRef(borrowed) if borrowed.is_mutable());
+            borrows.push(
+                is_mutable(&rust) || rust.type_args().next().is_some_and(|arg| is_mutable(&arg)),
+            );
+        }
+    }
+    // a mutable borrow of the table can't be held along with another one
+    if borrows.len() > 1 && borrows.contains(&true) {
+        return Err("borrows a resource mutably along with another borrow".to_string());
+    }
+    if let (Some(rust), Some(ty)) = (&rust_output, &output) {
+        let mut borrowed = false;
+        ty.visit(&mut |ty| borrowed |= matches!(ty.kind, WitTypeKind::Borrow(_)));
+        if borrowed {
+            return Err("return type: borrowed resource".to_string());
+        }
+        handle_position(rust, ty, true).map_err(|reason| format!("return type: {reason}"))?;
+    }
+
+    let config = match ctx.paths.get(&function.data.id) {
+        Some(path) => ctx.config.item(&path.join("::")),
+        None => ctx.config.defaults.clone(),
+    };
+    if config.iterator() == config::IteratorMode::Resource && returns_iterator(function) {
+        if let Some(WitType {
+            kind: WitTypeKind::List(item),
+            ..
+        }) = output
+        {
+            output = Some(WitType::builtin(WitTypeKind::Iterator(item)));
+        }
+    }
+
+    Ok(WitFunction {
+        name: names::ident(function.name()),
+        params,
+        output,
+        is_async,
+    })
+}
+
+/// The `#[non_exhaustive]` record within the Rust type `ty`, if any, as
+/// records of such structs only convert from Rust to WIT
+fn non_exhaustive_record(ty: &query::Item<&rustdoc_types::Type>) -> Option<String> {
+    match ty.type_kind() {
+        query::TypeKind::BorrowedRef(borrowed) => non_exhaustive_record(&borrowed.type_()),
+        query::TypeKind::Slice(item) => non_exhaustive_record(&item),
+        query::TypeKind::Array(array) => non_exhaustive_record(&array.type_()),
+        query::TypeKind::Tuple(types) => types.types().find_map(|ty| non_exhaustive_record(&ty)),
+        query::TypeKind::ResolvedPath(path) => {
+            if let Some(record) = ty.type_args().find_map(|arg| non_exhaustive_record(&arg)) {
+                return Some(record);
+            }
+            let struct_ = path.struct_item()?;
+            let is_record = matches!(
+                struct_.struct_kind(),
+                StructItemKind::StructPlain(plain) if !plain.fields_stripped()
+            );
+            (is_record && struct_.is_non_exhaustive()).then(|| struct_.name().to_string())
+        }
+        _ => None,
+    }
+}
+
+/// The output of the future `function` returns, e.g. `T` in
+/// `impl Future<Output = T>`
+fn future_output<'c>(
+    function: &query::Item<'c, &'c rustdoc_types::Function>,
+) -> Option<query::Item<'c, &'c rustdoc_types::Type>> {
+    match function.output()?.type_kind() {
+        query::TypeKind::ImplTrait(bounds) => bounds.binding(FUTURE_PATH, "Output"),
+        _ => None,
+    }
+}
+
+/// A zero-argument function returning the value of a constant or static,
+/// e.g. `red: func() -> color`
+fn getter(
+    ctx: &Context,
+    name: &str,
+    ty: &query::Item<&rustdoc_types::Type>,
+) -> Result<WitFunction, String> {
+    Ok(WitFunction {
+        name: names::ident(name),
+        params: Vec::new(),
+        output: Some(wit_type(ctx, ty)?),
+        is_async: false,
+    })
+}
+
+/// A trait impl from [`CONVERSIONS`], see [`conversion`]
+struct Conversion<'c> {
+    from: query::Item<'c, &'c rustdoc_types::Type>,
+    to: query::Item<'c, &'c rustdoc_types::Type>,
+    /// The `Error` associated type of `TryFrom` and `TryInto`
+    error: Option<query::Item<'c, &'c rustdoc_types::Type>>,
+    wit: WitFunction,
+}
+
+/// The function converting between the types of a `From`, `Into`, `TryFrom`
+/// or `TryInto` impl, named after the implementing type, e.g.
+/// `color-into-list-f32: func(value: color) -> list<f32>` for
+/// `impl Into<[f32; 4]> for Color`, or
+/// `key-try-from-u32: func(value: u32) -> result<key, error>` for
+/// `impl TryFrom<u32> for Key`
+///
+/// `None` if neither type is exported.
+fn conversion<'c>(
+    ctx: &Context,
+    impl_: &query::Item<'c, &'c Impl>,
+    kind: &str,
+) -> Result<Option<Conversion<'c>>, String> {
+    let self_type = impl_.for_();
+    let arg = impl_.trait_args().next().ok_or("no type argument")?;
+    let (from, to) = match kind {
+        "from" | "try-from" => (arg, self_type),
+        _ => (self_type, arg),
+    };
+    let is_exported = |ty: &query::Item<&rustdoc_types::Type>| match ty.type_kind() {
+        query::TypeKind::ResolvedPath(path) => ctx.paths.contains_key(path.id()),
+        _ => false,
+    };
+    if !is_exported(&from) && !is_exported(&to) {
+        return Ok(None);
+    }
+    if let Some(record) = non_exhaustive_record(&from) {
+        return Err(format!(
+            "`{record}` is `#[non_exhaustive]`, so it can't be built from WIT"
+        ));
+    }
+
+    let wit_from = wit_type(ctx, &from)?;
+    let wit_to = wit_type(ctx, &to)?;
+    // the value would be converted rather than taken out of the host's
+    // resource table, e.g. `camera-into-camera2-d`
+    if has_handle(&wit_from) || has_handle(&wit_to) {
+        return Err("converts a resource, which conversions take by value".to_string());
+    }
+    let (error, output) = match kind {
+        "try-from" | "try-into" => {
+            let error = impl_
+                .assoc_types()
+                .find(|assoc| assoc.name() == "Error")
+                .and_then(|assoc| assoc.default())
+                .ok_or("no `Error` type")?;
+            let err = match wit_type(ctx, &error)?.kind {
+                WitTypeKind::Tuple(types) if types.is_empty() => None,
+                kind => Some(Box::new(WitType::builtin(kind))),
+            };
+            let output = WitTypeKind::Result {
+                ok: Some(Box::new(wit_to.clone())),
+                err,
+            };
+            (Some(error), WitType::builtin(output))
+        }
+        _ => (None, wit_to.clone()),
+    };
+    let name = match kind {
+        "from" | "try-from" => format!(
+            "{}-{kind}-{}",
+            wit_to.kind.print_ref(),
+            wit_from.kind.print_ref()
+        ),
+        _ => format!(
+            "{}-{kind}-{}",
+            wit_from.kind.print_ref(),
+            wit_to.kind.print_ref()
+        ),
+    };
+    Ok(Some(Conversion {
+        from,
+        to,
+        error,
+        wit: WitFunction {
+            name: wit_ident(&name),
+            params: vec![("value".to_string(), wit_from)],
+            output: Some(output),
+            is_async: false,
+        },
+    }))
+}
+
+/// A trait impl from [`OPERATORS`], see [`operator`]
+struct Operator<'c> {
+    /// The operand on the right, e.g. `f32` in `impl Mul<f32> for Vec2`
+    rhs: Option<query::Item<'c, &'c rustdoc_types::Type>>,
+    /// The `Output` associated type, `None` for comparisons
+    output: Option<query::Item<'c, &'c rustdoc_types::Type>>,
+    wit: WitFunction,
+}
+
+/// The function applying the operator `op` of an impl for `ctx.self_type`,
+/// e.g. `vec2-add: func(a: vec2, b: vec2) -> vec2` for `impl Add for Vec2`
+///
+/// `eq` returns a `bool` and `cmp` the `Ordering` of `partial_cmp` as an
+/// `option<s8>` of -1, 0 or 1.
+fn operator<'c>(
+    ctx: &Context,
+    impl_: &query::Item<'c, &'c Impl>,
+    op: &str,
+    method: &str,
+) -> Result<Operator<'c>, String> {
+    let krate = impl_.krate();
+    let method = impl_
+        .items()
+        .find_map(|(_, item)| {
+            let inner = item.inner.as_function()?;
+            (item.name.as_deref() == Some(method)).then(|| query::Item::new(krate, item, inner))
+        })
+        .ok_or_else(|| format!("no `{method}` method"))?;
+    let a = wit_type(ctx, &impl_.for_())?;
+    let rhs = method.params().next().map(|(_, ty)| match ty.type_kind() {
+        query::TypeKind::BorrowedRef(borrowed) => borrowed.type_(),
+        _ => ty,
+    });
+    let record = non_exhaustive_record(&impl_.for_())
+        .or_else(|| rhs.as_ref().and_then(non_exhaustive_record));
+    if let Some(record) = record {
+        return Err(format!(
+            "`{record}` is `#[non_exhaustive]`, so it can't be built from WIT"
+        ));
+    }
+    let output = match op {
+        "eq" | "cmp" => None,
+        _ => Some(
+            impl_
+                .assoc_types()
+                .find(|assoc| assoc.name() == "Output")
+                .and_then(|assoc| assoc.default())
+                .ok_or("no `Output` type")?,
+        ),
+    };
+
+    let mut params = vec![("a".to_string(), a.clone())];
+    if let Some(rhs) = &rhs {
+        let name = if op == "index" { "index" } else { "b" };
+        params.push((name.to_string(), wit_type(ctx, rhs)?));
+    }
+    let wit_output = match (op, &output) {
+        ("cmp", _) => WitType::builtin(WitTypeKind::Option(Box::new(WitType::builtin(
+            WitTypeKind::S8,
+        )))),
+        (_, Some(output)) => wit_type(ctx, output)?,
+        (_, None) => WitType::builtin(WitTypeKind::Bool),
+    };
+    // the operator takes its operands by value, out of the host's resource
+    // table, e.g. `texture2-d-eq`
+    if params
+        .iter()
+        .map(|(_, ty)| ty)
+        .chain([&wit_output])
+        .any(has_handle)
+    {
+        return Err("resource operands or result, which the operator would consume".to_string());
+    }
+    Ok(Operator {
+        rhs,
+        output,
+        wit: WitFunction {
+            name: wit_ident(&format!("{}-{op}", a.kind.print_ref())),
+            params,
+            output: Some(wit_output),
+            is_async: false,
+        },
+    })
+}
+
+/// Functions for the std traits the type `ty` at `rust_path` implements, the
+/// ones enabled by the `helpers` setting, e.g. `color-to-string: func(value: color) -> string`
+fn helpers<'c>(
+    ctx: &Context,
+    rust_path: &str,
+    ty: &WitType,
+    trait_impls: impl Iterator<Item = query::Item<'c, &'c Impl>>,
+) -> Vec<(config::Helper, WitFunction)> {
+    let config = ctx.config.item(rust_path);
+    let implemented = trait_impls
+        .filter_map(|impl_| {
+            let trait_ = impl_.trait_()?;
+            HELPERS
+                .iter()
+                .find_map(|(path, helper)| trait_.is(path).then_some(*helper))
+        })
+        .collect::<Vec<_>>();
+    // resources are lent rather than given up
+    let value = || {
+        let kind = match &ty.kind {
+            WitTypeKind::Resource(name) => WitTypeKind::Borrow(name.clone()),
+            kind => kind.clone(),
+        };
+        vec![("value".to_string(), WitType { kind, ..ty.clone() })]
+    };
+    let string = || WitType::builtin(WitTypeKind::String);
+    HELPERS
+        .iter()
+        .map(|(_, helper)| *helper)
+        .filter(|helper| config.helpers().contains(helper) && implemented.contains(helper))
+        .filter_map(|helper| {
+            let (name, params, output) = match helper {
+                config::Helper::ToString => ("to-string", value(), string()),
+                config::Helper::DebugString => ("debug-string", value(), string()),
+                config::Helper::Default => ("default", Vec::new(), ty.clone()),
+                // records and variants are copied anyway
+                config::Helper::Clone => match ty.kind {
+                    WitTypeKind::Resource(_) => ("clone", value(), ty.clone()),
+                    _ => return None,
+                },
+                config::Helper::Parse => (
+                    "parse",
+                    vec![("s".to_string(), string())],
+                    WitType::builtin(WitTypeKind::Result {
+                        ok: Some(Box::new(ty.clone())),
+                        err: Some(Box::new(string())),
+                    }),
+                ),
+            };
+            let wit = WitFunction {
+                name: wit_ident(&format!("{}-{name}", ty.kind.print_ref())),
+                params,
+                output: Some(output),
+                is_async: false,
+            };
+            Some((helper, wit))
+        })
+        .collect()
+}
+
+/// The names of the flags of a `bitflags!`-style struct, which implements
+/// `BitOr` and has an associated constant of its own type per flag
+///
+/// The `flags` setting overrides the detection.
+fn flags(ctx: &Context, struct_: &query::Item<&Struct>, rust_path: &str) -> Option<Vec<String>> {
+    let is_bit_or = |impl_: query::Item<&Impl>| {
+        impl_
+            .trait_()
+            .is_some_and(|trait_| trait_.is(&["core", "ops", "bit", "BitOr"]))
+    };
+    let is_flags = ctx
+        .config
+        .item(rust_path)
+        .flags
+        .unwrap_or_else(|| struct_.trait_impls().any(is_bit_or));
+    if !is_flags {
+        return None;
+    }
+    let consts = struct_
+        .inherent_impls()
+        .flat_map(|impl_| impl_.assoc_consts().collect::<Vec<_>>())
+        .filter(|constant| match constant.type_().type_kind() {
+            query::TypeKind::ResolvedPath(path) => *path.id() == struct_.data.id,
+            query::TypeKind::Generic(generic) => generic == "Self",
+            _ => false,
+        })
+        .map(|constant| constant.name().to_string())
+        .collect::<Vec<_>>();
+    (!consts.is_empty()).then_some(consts)
+}
+
+/// Whether `function` returns `impl Iterator<Item = T>`
+fn returns_iterator(function: &query::Item<&rustdoc_types::Function>) -> bool {
+    match function.output().map(|ty| ty.type_kind()) {
+        Some(query::TypeKind::ImplTrait(bounds)) => bounds.binding(ITERATOR_PATH, "Item").is_some(),
+        _ => false,
+    }
+}
+
+/// A resource for guests to pull items from, e.g.
+/// `resource iterator-u8 { next: func() -> option<u8>; }`
+fn iterator_resource(name: &str, item: &WitType) -> Resource {
+    Resource {
+        name: name.to_string(),
+        methods: vec![WitFunction {
+            name: "next".to_string(),
+            params: Vec::new(),
+            output: Some(WitType::builtin(WitTypeKind::Option(Box::new(
+                item.clone(),
+            )))),
+            is_async: false,
+        }],
+        statics: Vec::new(),
+    }
+}
+
+/// Declare the WIT name of the item at `rust_path` in `scope`, and tell the
+/// host generator about it if it isn't just the kebab-case of `name`
+fn declare(
+    ctx: &mut Context,
+    scope: &mut names::Scope,
+    rust_path: &str,
+    name: &str,
+) -> Result<String, String> {
+    let wit_name = scope.declare(name)?;
+    if wit_name != name.to_case(Case::Kebab) {
+        ctx.host.rename(rust_path, &wit_name);
+    }
+    Ok(wit_name)
+}
+
+/// Join the alphanumeric runs of `text` with hyphens, escaping the result if
+/// it is a keyword, e.g. `list<u8>` is `list-u8` and `%type-eq` is `type-eq`
+///
+/// Names derived from other identifiers go through this, so the `%` of an
+/// escaped identifier doesn't end up in the middle of a name.
+fn wit_ident(text: &str) -> String {
+    let ident = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    names::escaped(ident)
+}
+
+/// The signature of a closure parameter, e.g. `impl Fn(f32) -> bool`, or
+/// `F` where `F: FnMut(&mut Ui)`
+fn closure_signature<'c>(
+    function: &query::Item<'c, &'c rustdoc_types::Function>,
+    ty: &query::Item<'c, &'c rustdoc_types::Type>,
+) -> Option<query::FnSignature<'c>> {
+    match ty.type_kind() {
+        query::TypeKind::ImplTrait(bounds) => bounds.fn_signature(),
+        query::TypeKind::Generic(name) => fn_signature(function, name),
+        _ => None,
+    }
+}
+
+/// The closure signature the type parameter `name` is bound by, if any
+fn fn_signature<'c>(
+    function: &query::Item<'c, &'c rustdoc_types::Function>,
+    name: &str,
+) -> Option<query::FnSignature<'c>> {
+    function
+        .generic_bounds(name)
+        .iter()
+        .find_map(|bounds| bounds.fn_signature())
+}
+
+/// The worlds of the library `name`: `{name}-host` where the interfaces of
+/// its modules are imported, `{name}-guest` where they are exported, and `{name}` including both
+///
+/// The interfaces of traits, which the library user implements, go the other
+/// way.
+fn worlds(ctx: &Context, name: &str, kind: WorldKind, includes: &[String]) -> Vec<World> {
+    let library = names::ident(name);
+    let user = user_interfaces(ctx);
+    let interfaces = ctx
+        .interfaces
+        .iter()
+        .map(|interface| interface.name.clone())
+        .filter(|name| !user.contains(name))
+        .collect::<Vec<_>>();
+    let host = World {
+        name: wit_ident(&format!("{library}-host")),
+        includes: includes.to_vec(),
+        imports: interfaces.clone(),
+        exports: user.clone(),
+    };
+    let guest = World {
+        name: wit_ident(&format!("{library}-guest")),
+        includes: includes.to_vec(),
+        imports: user,
+        exports: interfaces,
+    };
+    match kind {
+        WorldKind::Import => vec![host],
+        WorldKind::Export => vec![guest],
+        WorldKind::Combined => {
+            let combined = World {
+                name: library,
+                includes: vec![host.name.clone(), guest.name.clone()],
+                imports: Vec::new(),
+                exports: Vec::new(),
+            };
+            vec![host, guest, combined]
+        }
+    }
+}
+
+/// The interfaces of traits, which the library user implements
+fn user_interfaces(ctx: &Context) -> Vec<String> {
+    ctx.trait_interfaces.clone()
+}
+
+/// Unused parameters like `_` or `_event` still need a name
+fn param_name(name: &str, idx: usize) -> String {
+    match name.trim_start_matches('_') {
+        "" => format!("arg{idx}"),
+        name => names::ident(name),
+    }
+}
+
+/// Rust methods and the WIT functions they map to
+type Methods<'c> = Vec<(query::Item<'c, &'c rustdoc_types::Function>, WitFunction)>;
+
+/// A trait as an interface for guests to export, along with the required
+/// methods it is made of, or the reason it can't be
+///
+/// Provided methods keep their default implementation.
+fn trait_interface<'c>(
+    ctx: &Context,
+    trait_: &query::Item<'c, &'c rustdoc_types::Trait>,
+    name: &str,
+) -> Result<(Interface, Methods<'c>), String> {
+    if !trait_.generics().params.is_empty() {
+        return Err("generic trait".to_string());
+    }
+    if !trait_.bounds().is_empty() {
+        return Err("supertraits".to_string());
+    }
+    if trait_.is_unsafe() {
+        return Err("unsafe trait".to_string());
+    }
+
+    let mut methods = Vec::new();
+    for method in trait_.methods().filter(|method| !method.has_body()) {
+        let name = method.name();
+        let receiver = method
+            .receiver()
+            .ok_or_else(|| format!("`{name}` has no `self` receiver"))?;
+        let is_self =
+            |ty: &query::Item<&Type>| matches!(ty.type_kind(), query::TypeKind::Generic("Self"));
+        let receiver_ok = match receiver.type_kind() {
+            query::TypeKind::Generic("Self") => true,
+            query::TypeKind::BorrowedRef(borrowed) => is_self(&borrowed.type_()),
+            _ => false,
+        };
+        if !receiver_ok {
+            return Err(format!("`{name}` has an unsupported receiver"));
+        }
+        if let Some(query::TypeKind::BorrowedRef(_)) = method.output().map(|ty| ty.type_kind()) {
+            return Err(format!("`{name}` returns a reference"));
+        }
+        let wit = wit_function(ctx, &method).map_err(|reason| format!("`{name}`: {reason}"))?;
+        if wit.is_async {
+            return Err(format!("`{name}` is async"));
+        }
+        // the guest gets values, not handles to the host's resources
+        if wit.types().any(has_handle) {
+            return Err(format!("`{name}` takes or returns a resource"));
+        }
+        methods.push((method, wit));
+    }
+
+    if methods.is_empty() {
+        return Err("no required methods".to_string());
+    }
+
+    let interface = Interface {
+        name: names::ident(name),
+        functions: methods.iter().map(|(_, wit)| wit.clone()).collect(),
+    };
+    Ok((interface, methods))
+}
+
+/// A trait as a resource wrapping `dyn Trait`, along with its object safe
+/// methods, and the methods left out and why
+fn trait_resource<'c>(
+    ctx: &Context,
+    trait_: &query::Item<'c, &'c rustdoc_types::Trait>,
+    name: &str,
+) -> (Resource, Methods<'c>, Vec<(String, String)>) {
+    let mut methods = Vec::new();
+    let mut skipped = Vec::new();
+    for method in trait_.methods() {
+        let method_name = names::ident(method.name());
+        let is_self =
+            |ty: &query::Item<&Type>| matches!(ty.type_kind(), query::TypeKind::Generic("Self"));
+        let by_ref = match method.receiver().map(|ty| ty.type_kind()) {
+            Some(query::TypeKind::BorrowedRef(borrowed)) => is_self(&borrowed.type_()),
+            _ => false,
+        };
+        if !by_ref {
+            skipped.push((method_name, "not callable on a trait object".to_string()));
+            continue;
+        }
+        let sized = method
+            .where_bounds("Self")
+            .any(|bounds| bounds.has_bound(SIZED_PATH));
+        if sized {
+            skipped.push((method_name, "requires `Self: Sized`".to_string()));
+            continue;
+        }
+        if let Some(query::TypeKind::BorrowedRef(_)) = method.output().map(|ty| ty.type_kind()) {
+            skipped.push((method_name, "returns a reference".to_string()));
+            continue;
+        }
+        match wit_function(ctx, &method) {
+            Ok(wit) if wit.is_async => skipped.push((method_name, "async method".to_string())),
+            Ok(wit) => methods.push((method, wit)),
+            Err(reason) => skipped.push((method_name, reason)),
+        }
+    }
+
+    let resource = Resource {
+        name: name.to_string(),
+        methods: methods.iter().map(|(_, wit)| wit.clone()).collect(),
+        statics: Vec::new(),
+    };
+    (resource, methods, skipped)
+}
+
+/// A method or associated function of a library type, exported on its resource
+struct ResourceMethod<'c> {
+    function: query::Item<'c, &'c rustdoc_types::Function>,
+    /// The Rust path of the implemented trait, `None` for inherent impls
+    trait_: Option<String>,
+    wit: WitFunction,
+}
+
+/// The methods and associated functions of `struct_` from its inherent impls
+/// and impls of non-std traits, with unique WIT names, along with the ones
+/// left out and why
+///
+/// Functions are named after their Rust name, unless the `method-names`
+/// setting says otherwise. When several have the same name, or the name of
+/// one in `taken`, trait methods are prefixed with the trait name, e.g.
+/// `shape-len`, and if that isn't enough, suffixed with their parameter
+/// types, e.g. `from-vec2` and `from-vec3`.
+fn resource_methods<'c>(
+    ctx: &Context,
+    struct_: &query::Item<'c, &'c Struct>,
+    rust_path: &str,
+    taken: &[String],
+) -> (Vec<ResourceMethod<'c>>, Vec<(String, String)>) {
+    let mut methods = Vec::new();
+    let mut skipped = Vec::new();
+    let impls = struct_.inherent_impls().chain(
+        struct_
+            .trait_impls()
+            .filter(|impl_| !impl_.is_synthetic() && !impl_.is_blanket()),
+    );
+    for impl_ in impls {
+        let trait_ = match impl_.trait_() {
+            None => None,
+            // std traits are exported as helpers, operators and conversions
+            Some(trait_) => match trait_.summary().map(|summary| &summary.path) {
+                Some(path) if !["core", "alloc", "std"].contains(&path[0].as_str()) => {
+                    Some(path.join("::"))
+                }
+                _ => continue,
+            },
+        };
+        for function in impl_.methods() {
+            let name = names::ident(function.name());
+            if trait_.is_none() && *function.visibility() != Visibility::Public {
+                continue;
+            }
+            let by_ref = match function.receiver().map(|ty| ty.type_kind()) {
+                None => true,
+                Some(query::TypeKind::BorrowedRef(borrowed)) => {
+                    matches!(
+                        borrowed.type_().type_kind(),
+                        query::TypeKind::Generic("Self")
+                    )
+                }
+                Some(_) => false,
+            };
+            if !by_ref {
+                skipped.push((name, "takes `self` by value".to_string()));
+                continue;
+            }
+            if let Some(query::TypeKind::BorrowedRef(_)) =
+                function.output().map(|ty| ty.type_kind())
+            {
+                skipped.push((name, "returns a reference".to_string()));
+                continue;
+            }
+            match wit_function(ctx, &function) {
+                Ok(wit) if wit.is_async => skipped.push((name, "async method".to_string())),
+                Ok(wit) => methods.push(ResourceMethod {
+                    function,
+                    trait_: trait_.clone(),
+                    wit,
+                }),
+                Err(reason) => skipped.push((name, reason)),
+            }
+        }
+    }
+    // inherent methods first, so they keep their names
+    methods.sort_by(|a, b| (&a.trait_, a.function.name()).cmp(&(&b.trait_, b.function.name())));
+
+    let overrides = ctx.config.item(rust_path).method_names.unwrap_or_default();
+    let mut fixed = Vec::with_capacity(methods.len());
+    for method in &mut methods {
+        let key = match &method.trait_ {
+            Some(trait_) => {
+                let trait_name = trait_.rsplit("::").next().unwrap_or(trait_);
+                format!("{trait_name}::{}", method.function.name())
+            }
+            None => method.function.name().to_string(),
+        };
+        match overrides.get(&key) {
+            Some(name) => {
+                method.wit.name = names::ident(name);
+                fixed.push(true);
+            }
+            None => fixed.push(false),
+        }
+    }
+    let colliding = |methods: &[ResourceMethod]| {
+        methods
+            .iter()
+            .enumerate()
+            .map(|(idx, method)| {
+                taken.contains(&method.wit.name)
+                    || methods
+                        .iter()
+                        .enumerate()
+                        .any(|(other, m)| other != idx && m.wit.name == method.wit.name)
+            })
+            .collect::<Vec<_>>()
+    };
+    for (idx, collides) in colliding(&methods).into_iter().enumerate() {
+        let method = &mut methods[idx];
+        if let (true, false, Some(trait_)) = (collides, fixed[idx], &method.trait_) {
+            let trait_name = trait_.rsplit("::").next().unwrap_or(trait_);
+            method.wit.name =
+                wit_ident(&format!("{}-{}", names::ident(trait_name), method.wit.name));
+        }
+    }
+    for (idx, collides) in colliding(&methods).into_iter().enumerate() {
+        let method = &mut methods[idx];
+        if collides && !fixed[idx] && method.trait_.is_some() {
+            for (_, ty) in &method.wit.params {
+                method.wit.name =
+                    wit_ident(&format!("{}-{}", method.wit.name, ty.kind.print_ref()));
+            }
+        }
+    }
+
+    // whatever still collides is left out, keeping the first
+    let mut names = taken.to_vec();
+    methods.retain(|method| {
+        if names.contains(&method.wit.name) {
+            skipped.push((
+                method.wit.name.clone(),
+                format!("`{}` is taken by another function", method.wit.name),
+            ));
+            return false;
+        }
+        names.push(method.wit.name.clone());
+        true
+    });
+    (methods, skipped)
+}
+
+/// Whether `ty` contains a trait object without a `Send` bound, e.g.
+/// `Box<dyn Camera>`
+fn has_unsend_trait_object(ty: &query::Item<&Type>) -> bool {
+    match ty.type_kind() {
+        query::TypeKind::DynTrait(dyn_) => !dyn_.traits().any(|path| path.is(SEND_PATH)),
+        query::TypeKind::Tuple(types) => types.types().any(|ty| has_unsend_trait_object(&ty)),
+        _ => ty.type_args().any(|arg| has_unsend_trait_object(&arg)),
+    }
+}
+
+/// Whether `bounds` include `Into<String>`
+fn into_string(bounds: &query::Item<&[rustdoc_types::GenericBound]>) -> bool {
+    bounds.bound_args(INTO_PATH).iter().any(|arg| {
+        matches!(arg.type_kind(), query::TypeKind::ResolvedPath(path) if path.is(STRING_PATH))
+    })
+}
+
+/// `ty`, unless it's `()`, which is `tuple<>` for [`wit_type`] but has no WIT
+/// equivalent within another type
+///
+/// A `()` result is no result, and the ok or error of a `Result<(), E>` is `_`.
+fn not_unit(ty: WitType) -> Result<WitType, String> {
+    match &ty.kind {
+        WitTypeKind::Tuple(types) if types.is_empty() => {
+            Err("`()` within another type".to_string())
+        }
+        _ => Ok(ty),
+    }
+}
+
+/// Whether `ty` is a resource handle, owned or borrowed
+fn is_handle(ty: &WitType) -> bool {
+    matches!(ty.kind, WitTypeKind::Resource(_) | WitTypeKind::Borrow(_))
+}
+
+/// Whether `ty` contains a resource handle
+fn has_handle(ty: &WitType) -> bool {
+    let mut found = false;
+    ty.visit(&mut |ty| found |= is_handle(ty));
+    found
+}
+
+/// `ty`, unless it contains a resource, which values copied between the
+/// guest and the host can't hold as they'd have to take it out of the host's
+/// resource table
+fn not_handle(ty: WitType) -> Result<WitType, String> {
+    match has_handle(&ty) {
+        true => Err("holds a resource".to_string()),
+        false => Ok(ty),
+    }
+}
+
+/// Check that the host can take the resources of the parameter or return
+/// type `ty`, of Rust type `rust`, out of its resource table or push them
+/// into it
+///
+/// That's the case of resources passed or returned themselves, or within an
+/// `Option`, or the `Ok` of a returned `Result`, e.g. `Option<&Texture2D>`.
+fn handle_position(
+    rust: &query::Item<&rustdoc_types::Type>,
+    ty: &WitType,
+    output: bool,
+) -> Result<(), String> {
+    if is_handle(ty) {
+        return Ok(());
+    }
+    let wrapped = match (rust.type_kind(), &ty.kind) {
+        (query::TypeKind::ResolvedPath(path), WitTypeKind::Option(inner))
+            if path.is(OPTION_PATH) =>
+        {
+            Some((inner, None))
+        }
+        (query::TypeKind::ResolvedPath(path), WitTypeKind::Result { ok: Some(ok), err })
+            if output && path.is(RESULT_PATH) =>
+        {
+            Some((ok, err.as_deref()))
+        }
+        _ => None,
+    };
+    let rest = match wrapped.filter(|(inner, _)| is_handle(inner)) {
+        Some((_, err)) => {
+            // `Option<Box<dyn Trait + Send>>` of the table isn't coerced to
+            // `Option<Box<dyn Trait>>`
+            let is_dyn = |ty: &query::Item<&rustdoc_types::Type>| {
+                matches!(ty.type_kind(), query::TypeKind::DynTrait(_))
+            };
+            let is_trait_object =
+                rust.type_args()
+                    .next()
+                    .is_some_and(|arg| match arg.type_kind() {
+                        query::TypeKind::BorrowedRef(borrowed) => is_dyn(&borrowed.type_()),
+                        _ => arg.type_args().next().is_some_and(|arg| is_dyn(&arg)),
+                    });
+            if is_trait_object && !output {
+                return Err("optional trait object".to_string());
+            }
+            err
+        }
+        None => Some(ty),
+    };
+    match rest.is_some_and(has_handle) {
+        true => Err("resource within another type".to_string()),
+        false => Ok(()),
+    }
+}
+
+/// Map a Rust type to a WIT type, or the reason it can't be
+fn wit_type(ctx: &Context, ty: &query::Item<&rustdoc_types::Type>) -> Result<WitType, String> {
+    let kind = match ty.type_kind() {
+        query::TypeKind::Primitive(primitive) => WitTypeKind::from_rust_type(primitive)
+            .ok_or_else(|| format!("primitive `{primitive}`"))?,
+        query::TypeKind::ResolvedPath(path) => {
+            let mut args = ty.type_args();
+            let mut arg = || {
+                args.next()
+                    .ok_or_else(|| format!("missing type argument for `{}`", path.name()))
+                    .and_then(|arg| wit_type(ctx, &arg))
+            };
+            if path.is(STRING_PATH) {
+                WitTypeKind::String
+            } else if path.is(OPTION_PATH) {
+                WitTypeKind::Option(Box::new(not_unit(arg()?)?))
+            } else if path.is(VEC_PATH) {
+                WitTypeKind::List(Box::new(not_unit(arg()?)?))
+            } else if path.is(BOX_PATH) {
+                return arg();
+            } else if path.is(RESULT_PATH) {
+                let ok = arg()?;
+                let err = arg()?;
+                let unit = |ty: WitType| match ty.kind {
+                    WitTypeKind::Tuple(types) if types.is_empty() => None,
+                    _ => Some(Box::new(ty)),
+                };
+                WitTypeKind::Result {
+                    ok: unit(ok),
+                    err: unit(err),
+                }
+            } else if let Some(alias) = path.type_alias_item() {
+                // e.g. `type Id = u64`, which WIT would need defined
+                if !alias.generics().params.is_empty() {
+                    return Err(format!("generic type alias `{}`", path.name()));
+                }
+                return wit_type(ctx, &alias.type_());
+            } else {
+                let (crate_id, name) = match (path.item(), path.summary()) {
+                    (Some(item), _) => (item.data.crate_id, item.maybe_name().map(str::to_string)),
+                    (None, Some(summary)) => (summary.crate_id, summary.path.last().cloned()),
+                    (None, None) => return Err(format!("unresolved path `{}`", path.name())),
+                };
+                match name {
+                    Some(name) if crate_id == ctx.root_crate_id => {
+                        if !is_defined(ctx, path.id()) {
+                            return Err(format!("`{}` isn't exported", names::ident(name)));
+                        }
+                        let kind = match ctx.resources.contains(path.id()) {
+                            true => WitTypeKind::Resource(names::ident(name)),
+                            false => WitTypeKind::Named(names::ident(name)),
+                        };
+                        return Ok(WitType {
+                            kind,
+                            functions: (),
+                            source: Source::Local(path.id().clone()),
+                        });
+                    }
+                    _ => return Err(format!("foreign type `{}`", path.name())),
+                }
+            }
+        }
+        query::TypeKind::BorrowedRef(borrowed) => {
+            let mut wit = wit_type(ctx, &borrowed.type_())?;
+            // resources, e.g. trait objects, are lent to the library rather
+            // than given up
+            if let WitTypeKind::Resource(name) = &wit.kind {
+                // the table holds `Box<dyn Trait + Send>`
+                if matches!(borrowed.type_().type_kind(), query::TypeKind::ResolvedPath(path) if path.is(BOX_PATH))
+                {
+                    return Err("borrowed `Box` of a trait object".to_string());
+                }
+                wit.kind = WitTypeKind::Borrow(name.clone());
+            }
+            // values are copied to the host, so only handles can be changed
+            // in place, e.g. `&mut self`
+            if borrowed.is_mutable() && !matches!(wit.kind, WitTypeKind::Borrow(_)) {
+                return Err("`&mut` of a value, whose changes wouldn't reach the guest".to_string());
+            }
+            return Ok(wit);
+        }
+        query::TypeKind::Tuple(types) => WitTypeKind::Tuple(
+            types
+                .types()
+                .map(|ty| not_unit(wit_type(ctx, &ty)?))
+                .collect::<Result<_, _>>()?,
+        ),
+        query::TypeKind::Slice(ty) => WitTypeKind::List(Box::new(not_unit(wit_type(ctx, &ty)?)?)),
+        query::TypeKind::Array(array) => {
+            WitTypeKind::List(Box::new(not_unit(wit_type(ctx, &array.type_())?)?))
+        }
+        query::TypeKind::Generic("Self") if ctx.self_type.is_some() => {
+            let id = ctx.self_type.as_ref().unwrap();
+            let name = ty
+                .krate()
+                .index
+                .get(id)
+                .and_then(|item| item.name.as_deref());
+            let kind = match ctx.resources.contains(id) {
+                true => WitTypeKind::Resource,
+                false => WitTypeKind::Named,
+            };
+            return match name {
+                Some(name) => Ok(WitType {
+                    kind: kind(names::ident(name)),
+                    functions: (),
+                    source: Source::Local(id.clone()),
+                }),
+                None => Err("unnamed `Self` type".to_string()),
+            };
+        }
+        query::TypeKind::Generic(generic) => return Err(format!("generic `{generic}`")),
+        query::TypeKind::DynTrait(dyn_) => {
+            // auto traits like `Send` are foreign, so the local trait is the principal one
+            let trait_ = dyn_.traits().find_map(|path| {
+                path.trait_item()
+                    .filter(|trait_| trait_.data.crate_id == ctx.root_crate_id)
+            });
+            let is_generic = trait_.as_ref().is_some_and(|trait_| {
+                trait_.generics().params.iter().any(|param| {
+                    !matches!(
+                        param.kind,
+                        rustdoc_types::GenericParamDefKind::Lifetime { .. }
+                    )
+                })
+            });
+            if is_generic {
+                return Err("trait object of a generic trait".to_string());
+            }
+            return match trait_ {
+                Some(trait_) => Ok(WitType {
+                    kind: WitTypeKind::Resource(names::ident(trait_.name())),
+                    functions: (),
+                    source: Source::Local(trait_.data.id.clone()),
+                }),
+                None => Err("trait object of a foreign trait".to_string()),
+            };
+        }
+        query::TypeKind::FunctionPointer(_) => {
+            return Err("function pointer (only closures can call back into the guest)".to_string())
+        }
+        query::TypeKind::ImplTrait(bounds) => {
+            // collected eagerly, see `IteratorMode` for iterator resources
+            if let Some(item) = bounds.binding(ITERATOR_PATH, "Item") {
+                WitTypeKind::List(Box::new(wit_type(ctx, &item)?))
+            } else if bounds.has_bound(DISPLAY_PATH) || into_string(&bounds) {
+                WitTypeKind::String
+            } else {
+                return Err("`impl Trait` without a WIT equivalent".to_string());
+            }
+        }
+        query::TypeKind::Infer => return Err("query::TypeKind::Infer".to_string()),
+        query::TypeKind::RawPointer(_) => return Err("query::TypeKind::RawPointer".to_string()),
+        query::TypeKind::QualifiedPath(qpath) => {
+            return match qpath.resolve(ctx.self_type.as_ref()) {
+                Some(ty) => wit_type(ctx, &ty),
+                None => Err(format!("unresolved associated type `{}`", qpath.name())),
+            };
+        }
+    };
+    Ok(WitType::builtin(kind))
+}
+
+/// A named group of functions, e.g. the exports a guest provides
+struct Interface {
+    name: String,
+    functions: Vec<WitFunction>,
+}
+
+/// `world name { include x; import y; export z; }`
+struct World {
+    name: String,
+    includes: Vec<String>,
+    imports: Vec<String>,
+    exports: Vec<String>,
+}
+
+impl World {
+    fn print(&self) -> impl std::fmt::Display {
+        let mut f = String::new();
+        writeln!(f, "world {name} {{", name = self.name).unwrap();
+        for include in &self.includes {
+            writeln!(f, "    include {include};").unwrap();
+        }
+        for import in &self.imports {
+            writeln!(f, "    import {import};").unwrap();
+        }
+        for export in &self.exports {
+            writeln!(f, "    export {export};").unwrap();
+        }
+        write!(f, "}}").unwrap();
+        f
+    }
+}
+
+#[derive(Clone)]
+struct WitFunction {
+    name: String,
+    params: Vec<(String, WitType)>,
+    output: Option<WitType>,
+    is_async: bool,
+}
+
+impl WitFunction {
+    /// The types of the parameters and the return type
+    fn types(&self) -> impl Iterator<Item = &WitType> {
+        self.params.iter().map(|(_, ty)| ty).chain(&self.output)
+    }
+
+    fn print(&self) -> impl std::fmt::Display {
+        self.print_as(if self.is_async { "async func" } else { "func" })
+    }
+
+    /// `func_kind` is e.g. `func` or `static func`
+    fn print_as(&self, func_kind: &str) -> String {
+        let mut f = String::new();
+        write!(f, "{name}: {func_kind}(", name = self.name).unwrap();
+        for (idx, (name, param)) in self.params.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ").unwrap();
+            }
+            write!(f, "{name}: {param}", param = param.kind.print_ref()).unwrap();
+        }
+        write!(f, ")").unwrap();
+        match &self.output {
+            Some(output) => write!(f, " -> {output};", output = output.kind.print_ref()).unwrap(),
+            None => write!(f, ";").unwrap(),
+        }
+        f
+    }
+}
+
+#[derive(Clone)]
+struct WitType {
+    kind: WitTypeKind,
+    functions: (),
+    source: Source,
+}
+
+impl WitType {
+    /// A type that doesn't come from the crate, e.g. a primitive or a `list`
+    fn builtin(kind: WitTypeKind) -> Self {
+        Self {
+            kind,
+            functions: (),
+            source: Source::Foreign(None),
+        }
+    }
+
+    /// Call `f` on this type and every type it is made of
+    fn visit(&self, f: &mut impl FnMut(&WitType)) {
+        f(self);
+        match &self.kind {
+            WitTypeKind::Tuple(types) => types.iter().for_each(|ty| ty.visit(f)),
+            WitTypeKind::List(ty) | WitTypeKind::Option(ty) | WitTypeKind::Iterator(ty) => {
+                ty.visit(f)
+            }
+            WitTypeKind::Result { ok, err } => {
+                ok.iter().chain(err).for_each(|ty| ty.visit(f));
+            }
+            WitTypeKind::TypeAlias(alias) => alias.ty.visit(f),
+            WitTypeKind::Record(record) => record.fields.iter().for_each(|(_, ty)| ty.visit(f)),
+            WitTypeKind::Variant(variant) => variant
+                .cases
+                .iter()
+                .filter_map(|(_, payload)| payload.as_ref())
+                .for_each(|ty| ty.visit(f)),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Source {
+    Local(Id),
+    Foreign(Option<Id>),
+}
+
+#[derive(Clone)]
+enum WitTypeKind {
+    U8,
+    U16,
+    U32,
+    U64,
+    S8,
+    S16,
     S32,
     S64,
     Float32,
@@ -356,23 +3098,92 @@ enum WitTypeKind {
     Char,
     Bool,
     String,
+    Tuple(Vec<WitType>),
+    List(Box<WitType>),
+    Option(Box<WitType>),
+    /// `None` for `_`, e.g. `result<_, error>`
+    Result {
+        ok: Option<Box<WitType>>,
+        err: Option<Box<WitType>>,
+    },
+    /// A reference to a type defined elsewhere
+    Named(String),
     Record(Record),
     Variant(Variant),
+    Enum(Enum),
+    Flags(Flags),
     Resource(String),
+    TypeAlias(TypeAlias),
+    /// A resource yielding items of this type, see [`iterator_resource`]
+    Iterator(Box<WitType>),
+    /// A handle to the resource of this name lent for a call, `borrow<name>`
+    Borrow(String),
+}
+
+#[derive(Clone)]
+struct Record {
+    name: String,
+    fields: Vec<(String, WitType)>,
+}
+
+#[derive(Clone)]
+struct Enum {
+    name: String,
+    /// Case names and their doc comments
+    cases: Vec<(String, Option<String>)>,
 }
 
-struct Record {
+/// Named bits, see [`flags`]
+#[derive(Clone)]
+struct Flags {
     name: String,
-    fields: Vec<(String, WitType)>,
+    flags: Vec<String>,
 }
 
+#[derive(Clone)]
 struct Variant {
     name: String,
-    fields: Vec<WitType>,
+    /// Case names and their payloads
+    cases: Vec<(String, Option<WitType>)>,
 }
 
+/// A resource with methods, e.g. a trait object
 struct Resource {
     name: String,
+    methods: Vec<WitFunction>,
+    /// `[static]` functions, e.g. getters of associated constants
+    statics: Vec<WitFunction>,
+}
+
+impl Resource {
+    fn print(&self) -> impl std::fmt::Display {
+        let mut f = String::new();
+        if self.methods.is_empty() && self.statics.is_empty() {
+            write!(f, "resource {name};", name = self.name).unwrap();
+            return f;
+        }
+        writeln!(f, "resource {name} {{", name = self.name).unwrap();
+        for method in &self.methods {
+            writeln!(f, "    {method}", method = method.print()).unwrap();
+        }
+        for function in &self.statics {
+            writeln!(
+                f,
+                "    {function}",
+                function = function.print_as("static func")
+            )
+            .unwrap();
+        }
+        write!(f, "}}").unwrap();
+        f
+    }
+}
+
+/// `type name = ty;`
+#[derive(Clone)]
+struct TypeAlias {
+    name: String,
+    ty: Box<WitType>,
 }
 
 impl WitTypeKind {
@@ -396,6 +3207,40 @@ impl WitTypeKind {
         }
     }
 
+    /// How the type is written where it is used, i.e. the name of a named type
+    fn print_ref(&self) -> String {
+        match self {
+            Self::Tuple(types) => {
+                let types = types
+                    .iter()
+                    .map(|ty| ty.kind.print_ref())
+                    .collect::<Vec<_>>();
+                format!("tuple<{}>", types.join(", "))
+            }
+            Self::List(ty) => format!("list<{}>", ty.kind.print_ref()),
+            Self::Option(ty) => format!("option<{}>", ty.kind.print_ref()),
+            Self::Result { ok, err } => match (ok, err) {
+                (None, None) => "result".to_string(),
+                (Some(ok), None) => format!("result<{}>", ok.kind.print_ref()),
+                (ok, Some(err)) => format!(
+                    "result<{}, {}>",
+                    ok.as_ref()
+                        .map_or("_".to_string(), |ok| ok.kind.print_ref()),
+                    err.kind.print_ref()
+                ),
+            },
+            Self::Named(name) | Self::Resource(name) => name.clone(),
+            Self::Borrow(name) => format!("borrow<{name}>"),
+            Self::Iterator(item) => wit_ident(&format!("iterator-{}", item.kind.print_ref())),
+            Self::Record(Record { name, .. })
+            | Self::Variant(Variant { name, .. })
+            | Self::Enum(Enum { name, .. })
+            | Self::Flags(Flags { name, .. })
+            | Self::TypeAlias(TypeAlias { name, .. }) => name.clone(),
+            _ => self.print().to_string(),
+        }
+    }
+
     fn print(&self) -> impl std::fmt::Display {
         match self {
             Self::U8 => "u8".to_string(),
@@ -406,11 +3251,18 @@ impl WitTypeKind {
             Self::S16 => "s16".to_string(),
             Self::S32 => "s32".to_string(),
             Self::S64 => "s64".to_string(),
-            Self::Float32 => "float32".to_string(),
-            Self::Float64 => "float64".to_string(),
+            Self::Float32 => "f32".to_string(),
+            Self::Float64 => "f64".to_string(),
             Self::Char => "char".to_string(),
             Self::Bool => "bool".to_string(),
             Self::String => "string".to_string(),
+            Self::Tuple(_)
+            | Self::List(_)
+            | Self::Option(_)
+            | Self::Result { .. }
+            | Self::Named(_)
+            | Self::Borrow(_)
+            | Self::Iterator(_) => self.print_ref(),
             Self::Record(record) => {
                 let mut f = String::new();
                 write!(f, "record {name}", name = record.name).unwrap();
@@ -422,35 +3274,72 @@ impl WitTypeKind {
                         if idx > 0 {
                             write!(f, ", ").unwrap();
                         }
-                        write!(f, "{name}: {ty}", ty = ty.kind.print()).unwrap();
+                        write!(f, "{name}: {ty}", ty = ty.kind.print_ref()).unwrap();
                     }
-                    write!(f, "}};").unwrap();
+                    write!(f, "}}").unwrap();
                 }
                 f
             }
             Self::Variant(variant) => {
                 let mut f = String::new();
                 write!(f, "variant {name}", name = variant.name).unwrap();
-                if variant.fields.is_empty() {
+                if variant.cases.is_empty() {
                     write!(f, ";").unwrap();
                 } else {
                     write!(f, " {{").unwrap();
-                    for (idx, ty) in variant.fields.iter().enumerate() {
+                    for (idx, (name, payload)) in variant.cases.iter().enumerate() {
                         if idx > 0 {
                             write!(f, ", ").unwrap();
                         }
-                        write!(f, "{ty}", ty = ty.kind.print()).unwrap();
+                        write!(f, "{name}").unwrap();
+                        if let Some(payload) = payload {
+                            write!(f, "({ty})", ty = payload.kind.print_ref()).unwrap();
+                        }
                     }
                     write!(f, "}}").unwrap();
                 }
                 f
             }
+            Self::Enum(enum_) if enum_.cases.iter().all(|(_, doc)| doc.is_none()) => {
+                let cases = enum_
+                    .cases
+                    .iter()
+                    .map(|(case, _)| case.as_str())
+                    .collect::<Vec<_>>();
+                format!("enum {name} {{{}}}", cases.join(", "), name = enum_.name)
+            }
+            Self::Enum(enum_) => {
+                let mut f = String::new();
+                writeln!(f, "enum {name} {{", name = enum_.name).unwrap();
+                for (case, doc) in &enum_.cases {
+                    if let Some(doc) = doc {
+                        writeln!(f, "    /// {doc}").unwrap();
+                    }
+                    writeln!(f, "    {case},").unwrap();
+                }
+                write!(f, "}}").unwrap();
+                f
+            }
+            Self::Flags(flags) => {
+                format!(
+                    "flags {name} {{{}}}",
+                    flags.flags.join(", "),
+                    name = flags.name
+                )
+            }
             Self::Resource(resource) => {
                 let mut f = String::new();
                 write!(f, "resource {name}", name = resource).unwrap();
                 write!(f, ";").unwrap();
                 f
             }
+            Self::TypeAlias(alias) => {
+                format!(
+                    "type {name} = {ty};",
+                    name = alias.name,
+                    ty = alias.ty.kind.print_ref()
+                )
+            }
         }
     }
 }
@@ -1114,3 +4003,1766 @@ fn map_rust_primitive_to_wit(ty: &str) -> String {
         _ => format!("todo<{ty}>", ty = ty.to_case(Case::Kebab)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The rustdoc JSON of macroquad the tests run on
+    fn fixture() -> Crate {
+        serde_json::from_reader(std::fs::File::open("macroquad.json").unwrap()).unwrap()
+    }
+
+    /// The id of the library item called `name`
+    fn local_id(krate: &Crate, name: &str) -> Id {
+        krate
+            .index
+            .values()
+            .find(|item| item.name.as_deref() == Some(name) && item.crate_id == 0)
+            .unwrap()
+            .id
+            .clone()
+    }
+
+    /// The id of the item at the fully qualified `path`, e.g. [`VEC_PATH`]
+    fn path_id(krate: &Crate, path: &[&str]) -> Id {
+        krate
+            .paths
+            .iter()
+            .find(|(_, summary)| summary.path == path)
+            .unwrap()
+            .0
+            .clone()
+    }
+
+    /// `name<arg>`, e.g. `Vec<T>`
+    fn generic(name: &str, id: Id, arg: Type) -> Type {
+        Type::ResolvedPath(rustdoc_types::Path {
+            name: name.to_string(),
+            id,
+            args: Some(Box::new(GenericArgs::AngleBracketed {
+                args: vec![GenericArg::Type(arg)],
+                bindings: Vec::new(),
+            })),
+        })
+    }
+
+    /// Add the public field `name` of type `ty` to the plain struct `struct_`
+    fn push_field(krate: &mut Crate, struct_: &Id, name: &str, ty: Type) {
+        let field = Id(format!("{}-{name}", struct_.0));
+        krate.index.insert(
+            field.clone(),
+            Item {
+                id: field.clone(),
+                crate_id: 0,
+                name: Some(name.to_string()),
+                span: None,
+                visibility: Visibility::Public,
+                docs: None,
+                links: HashMap::new(),
+                attrs: Vec::new(),
+                deprecation: None,
+                inner: ItemEnum::StructField(ty),
+            },
+        );
+        let ItemEnum::Struct(Struct {
+            kind: StructKind::Plain { fields, .. },
+            ..
+        }) = &mut krate.index.get_mut(struct_).unwrap().inner
+        else {
+            unreachable!()
+        };
+        fields.push(field);
+    }
+
+    fn context(query: &query::CrateQuery) -> Context {
+        let root = query.root();
+        let mut paths = HashMap::new();
+        public_paths(&root, &[root.name().to_string()], &mut paths);
+        Context {
+            root_crate_id: root.data.crate_id,
+            paths,
+            config: config::Config::default(),
+            async_: false,
+            host: host::HostCode::default(),
+            guest: guest::GuestCode::default(),
+            trait_objects: BTreeMap::new(),
+            iterators: BTreeMap::new(),
+            self_type: None,
+            trait_interfaces: Vec::new(),
+            interfaces: Vec::new(),
+            type_interfaces: HashMap::new(),
+            defined_types: None,
+            resources: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn error_enum_to_variant() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let error = query
+            .all_enums()
+            .find(|enum_| enum_.name() == "Error")
+            .unwrap();
+        assert!(is_error_type(&error));
+
+        let (wit, arms) = error_variant(&error, "Error", "macroquad::Error");
+        assert_eq!(
+            wit.kind.print().to_string(),
+            "variant error {font-error(string), file-error(string), shader-error(string), \
+             image-error(string), unknown-error(string), message(string)}"
+        );
+        assert_eq!(
+            arms[1],
+            (
+                "macroquad::Error::FileError { .. }".to_string(),
+                "file-error".to_string()
+            )
+        );
+
+        let not_error = query
+            .all_enums()
+            .find(|enum_| enum_.name() == "KeyCode")
+            .unwrap();
+        assert!(!is_error_type(&not_error));
+    }
+
+    #[test]
+    fn trait_to_interface() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let ctx = context(&query);
+        let clipboard = query
+            .all_traits()
+            .find(|trait_| trait_.name() == "ClipboardObject")
+            .unwrap();
+
+        let (interface, methods) = trait_interface(&ctx, &clipboard, "ClipboardObject").unwrap();
+        assert_eq!(interface.name, "clipboard-object");
+        let functions = interface
+            .functions
+            .iter()
+            .map(|function| function.print().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            functions,
+            ["get: func() -> option<string>;", "set: func(data: string);"]
+        );
+        assert_eq!(methods.len(), 2);
+
+        let node = query
+            .all_traits()
+            .find(|trait_| trait_.name() == "Node")
+            .unwrap();
+        assert!(trait_interface(&ctx, &node, "Node").is_err());
+    }
+
+    #[test]
+    fn closure_param_rejected() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let ctx = context(&query);
+        // e.g. `Group::ui<F: FnOnce(&mut Ui)>(self, ui: &mut Ui, f: F)`
+        let reasons = query
+            .all_functions()
+            .filter_map(|function| wit_function(&ctx, &function).err())
+            .collect::<Vec<_>>();
+        assert!(reasons.iter().any(|reason| reason
+            == "parameter `f`: closure, which the host can't call during a guest call"));
+    }
+
+    #[test]
+    fn mutable_borrow_of_value() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        // `Ui::checkbox(&mut self, id: Id, label: &str, data: &mut bool)`
+        let ui = local_id(&krate, "Ui");
+        let checkbox = query
+            .all_functions()
+            .find(|function| {
+                function.name() == "checkbox" && function.params().any(|(name, _)| name == "data")
+            })
+            .unwrap();
+        ctx.self_type = Some(ui);
+        assert_eq!(
+            wit_function(&ctx, &checkbox).err().unwrap(),
+            "parameter `data`: `&mut` of a value, whose changes wouldn't reach the guest"
+        );
+    }
+
+    #[test]
+    fn dyn_trait_to_resource() {
+        let mut krate = fixture();
+        // `where Self: Send` keeps a method callable on the trait object,
+        // `where Self: Sized` doesn't
+        let camera = krate
+            .index
+            .values()
+            .find_map(|item| match &item.inner {
+                ItemEnum::Trait(trait_) if item.name.as_deref() == Some("Camera") => {
+                    Some(trait_.items.clone())
+                }
+                _ => None,
+            })
+            .unwrap();
+        for id in camera {
+            let method = krate.index.get_mut(&id).unwrap();
+            let bound = match method.name.as_deref() {
+                Some("depth_enabled") => ("Send", "2:2775:240"),
+                Some("viewport") => ("Sized", "2:2782:2009"),
+                _ => continue,
+            };
+            let ItemEnum::Function(function) = &mut method.inner else {
+                unreachable!()
+            };
+            let bound = rustdoc_types::GenericBound::TraitBound {
+                trait_: rustdoc_types::Path {
+                    name: bound.0.to_string(),
+                    id: Id(bound.1.to_string()),
+                    args: None,
+                },
+                generic_params: Vec::new(),
+                modifier: rustdoc_types::TraitBoundModifier::None,
+            };
+            function.generics.where_predicates.push(
+                rustdoc_types::WherePredicate::BoundPredicate {
+                    type_: Type::Generic("Self".to_string()),
+                    bounds: vec![bound],
+                    generic_params: Vec::new(),
+                },
+            );
+        }
+        let query = query::CrateQuery::new(&krate);
+        let ctx = context(&query);
+
+        // `set_camera(camera: &dyn Camera)`
+        let set_camera = query
+            .all_functions()
+            .find(|function| function.name() == "set_camera" && function.params().count() == 1)
+            .unwrap();
+        let wit = wit_function(&ctx, &set_camera).unwrap();
+        assert_eq!(
+            wit.print().to_string(),
+            "set-camera: func(camera: borrow<camera>);"
+        );
+
+        let camera = query
+            .all_traits()
+            .find(|trait_| trait_.name() == "Camera")
+            .unwrap();
+        let (resource, methods, skipped) = trait_resource(&ctx, &camera, "camera");
+        assert_eq!(
+            resource.print().to_string(),
+            "resource camera {\n    depth-enabled: func() -> bool;\n}"
+        );
+        assert_eq!(methods.len(), 1);
+        assert!(skipped.contains(&("viewport".to_string(), "requires `Self: Sized`".to_string())));
+    }
+
+    #[test]
+    fn trait_object_output() {
+        let mut krate = fixture();
+        // make `set_camera` return `Box<dyn Camera>`
+        let (set_camera, object) = krate
+            .index
+            .iter()
+            .find_map(|(id, item)| match (&item.name, &item.inner) {
+                (Some(name), ItemEnum::Function(function)) if name == "set_camera" => {
+                    match function.decl.inputs.as_slice() {
+                        [(_, Type::BorrowedRef { type_, .. })] => match &**type_ {
+                            Type::DynTrait(object) => Some((id.clone(), object.clone())),
+                            _ => None,
+                        },
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .unwrap();
+        let boxed = |object: rustdoc_types::DynTrait| {
+            generic("Box", path_id(&krate, BOX_PATH), Type::DynTrait(object))
+        };
+        let mut send = object.clone();
+        send.traits.push(rustdoc_types::PolyTrait {
+            trait_: rustdoc_types::Path {
+                name: "Send".to_string(),
+                id: path_id(&krate, SEND_PATH),
+                args: None,
+            },
+            generic_params: Vec::new(),
+        });
+        let (unsend, send) = (boxed(object), boxed(send));
+        let mut with_output = |output: Type| {
+            let ItemEnum::Function(function) = &mut krate.index.get_mut(&set_camera).unwrap().inner
+            else {
+                unreachable!()
+            };
+            function.decl.output = Some(output);
+            krate.clone()
+        };
+        let (unsend, send) = (with_output(unsend), with_output(send));
+
+        // the resource table only holds `Send` values
+        let query = query::CrateQuery::new(&unsend);
+        let ctx = context(&query);
+        let function = query
+            .all_functions()
+            .find(|function| function.name() == "set_camera" && function.params().count() == 1)
+            .unwrap();
+        assert_eq!(
+            wit_function(&ctx, &function).err().unwrap(),
+            "return type: trait object that isn't `Send`"
+        );
+
+        let query = query::CrateQuery::new(&send);
+        let mut ctx = context(&query);
+        let function = query
+            .all_functions()
+            .find(|function| function.name() == "set_camera" && function.params().count() == 1)
+            .unwrap();
+        let wit = wit_function(&ctx, &function).unwrap();
+        assert_eq!(
+            wit.print().to_string(),
+            "set-camera: func(camera: borrow<camera>) -> camera;"
+        );
+        ctx.host
+            .function("macroquad::camera::set_camera", &function, &wit);
+        let camera = query
+            .all_traits()
+            .find(|trait_| trait_.name() == "Camera")
+            .unwrap();
+        let (resource, methods, _) = trait_resource(&ctx, &camera, "camera");
+        ctx.host
+            .trait_object("macroquad::camera::Camera", &resource, &methods);
+        let host = ctx.host.print().to_string();
+        assert!(host.contains("pub type Camera = Box<dyn macroquad::camera::Camera + Send>;"));
+        assert!(host.contains(
+            "    fn set_camera(&mut self, camera: wasmtime::component::Resource<wit::Camera>) -> wasmtime::Result<wasmtime::component::Resource<wit::Camera>> {\n        let camera = self.table.get(&camera)?;\n        let value = macroquad::camera::set_camera(&**camera);\n        Ok(self.table.push(value)?)\n    }"
+        ));
+    }
+
+    #[test]
+    fn impl_trait_output() {
+        let mut krate = fixture();
+        // `find_nodes_by_type<T>() -> impl Iterator<Item = RefMut<T>>` is
+        // generic, so make it `find_nodes_by_type() -> impl Iterator<Item = Color>`
+        let find_nodes = local_id(&krate, "find_nodes_by_type");
+        let color = Type::ResolvedPath(rustdoc_types::Path {
+            name: "Color".to_string(),
+            id: local_id(&krate, "Color"),
+            args: None,
+        });
+        let ItemEnum::Function(function) = &mut krate.index.get_mut(&find_nodes).unwrap().inner
+        else {
+            unreachable!()
+        };
+        function.generics.params.clear();
+        let Some(Type::ImplTrait(bounds)) = &mut function.decl.output else {
+            unreachable!()
+        };
+        let rustdoc_types::GenericBound::TraitBound { trait_, .. } = &mut bounds[0] else {
+            unreachable!()
+        };
+        let Some(GenericArgs::AngleBracketed { bindings, .. }) = trait_.args.as_deref_mut() else {
+            unreachable!()
+        };
+        bindings[0].binding =
+            rustdoc_types::TypeBindingKind::Equality(rustdoc_types::Term::Type(color));
+
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let find_nodes = query
+            .all_functions()
+            .find(|function| function.name() == "find_nodes_by_type")
+            .unwrap();
+        assert!(returns_iterator(&find_nodes));
+        let wit = wit_function(&ctx, &find_nodes).unwrap();
+        assert_eq!(
+            wit.print().to_string(),
+            "find-nodes-by-type: func() -> list<color>;"
+        );
+
+        ctx.config = serde_json::from_str(r#"{"defaults": {"iterator": "resource"}}"#).unwrap();
+        let wit = wit_function(&ctx, &find_nodes).unwrap();
+        assert_eq!(
+            wit.print().to_string(),
+            "find-nodes-by-type: func() -> iterator-color;"
+        );
+        let Some(WitTypeKind::Iterator(item)) = wit.output.as_ref().map(|ty| &ty.kind) else {
+            unreachable!()
+        };
+        let resource = iterator_resource("iterator-color", item);
+        assert_eq!(
+            resource.print().to_string(),
+            "resource iterator-color {\n    next: func() -> option<color>;\n}"
+        );
+
+        let rust_path = "macroquad::experimental::scene::find_nodes_by_type";
+        ctx.host.function(rust_path, &find_nodes, &wit);
+        ctx.host.iterator(&resource, item);
+        let host = ctx.host.print().to_string();
+        assert!(
+            host.contains("pub type IteratorColor = Box<dyn Iterator<Item = wit::Color> + Send>;")
+        );
+        assert!(host.contains(
+            "        let iterator = Box::new(macroquad::experimental::scene::find_nodes_by_type().map(|item| item.into())) as wit::IteratorColor;\n        Ok(self.table.push(iterator)?)"
+        ));
+        assert!(host.contains(
+            "        let iterator = self.table.get_mut(&self_)?;\n        Ok(iterator.next())"
+        ));
+
+        let config: config::Config = serde_json::from_str(
+            r#"{"items": {"macroquad::experimental::scene::find_nodes_by_type": {"iterator": "resource"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.item(rust_path).iterator(),
+            config::IteratorMode::Resource
+        );
+        assert_eq!(
+            config.item("macroquad::ui::root_ui").iterator(),
+            config::IteratorMode::List
+        );
+    }
+
+    #[test]
+    fn async_function() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let load_file = query
+            .all_functions()
+            .find(|function| function.name() == "load_file")
+            .unwrap();
+
+        let reason = wit_function(&ctx, &load_file).err().unwrap();
+        assert_eq!(reason, "async function (export with `--async`)");
+
+        ctx.async_ = true;
+        let wit = wit_function(&ctx, &load_file).unwrap();
+        assert_eq!(
+            wit.print().to_string(),
+            "load-file: async func(path: string) -> result<list<u8>, error>;"
+        );
+    }
+
+    #[test]
+    fn constant_to_getter() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let ctx = context(&query);
+        let colors = query
+            .all_modules()
+            .find(|module| module.name() == "colors")
+            .unwrap();
+        let (name, red) = exported(&colors, |inner| inner.as_constant())
+            .into_iter()
+            .find(|(name, _)| name == "RED")
+            .unwrap();
+        let wit = getter(&ctx, &name, &red.type_()).unwrap();
+        assert_eq!(wit.print().to_string(), "red: func() -> color;");
+    }
+
+    #[test]
+    fn associated_items() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let ctx = context(&query);
+
+        // `impl Iterator for MagicVecIterator { type Item = RefMutAny<'static>; }`
+        let item = query
+            .all_trait_impls()
+            .filter(|impl_| !impl_.is_synthetic() && !impl_.is_blanket())
+            .filter(|impl_| {
+                impl_
+                    .trait_()
+                    .is_some_and(|trait_| trait_.is(ITERATOR_PATH))
+            })
+            .flat_map(|impl_| impl_.assoc_types().collect::<Vec<_>>())
+            .find(|assoc| assoc.name() == "Item")
+            .unwrap();
+        let ty = wit_type(&ctx, &item.default().unwrap()).unwrap();
+        assert_eq!(ty.kind.print_ref(), "ref-mut-any");
+    }
+
+    #[test]
+    fn associated_constants() {
+        let mut krate = fixture();
+        // macroquad has no associated constants, so give `Texture2D` a
+        // `const MAX_SIZE: u32`
+        let texture = local_id(&krate, "Texture2D");
+        let impl_id = krate
+            .index
+            .iter()
+            .find(|(_, item)| match &item.inner {
+                ItemEnum::Impl(impl_) => {
+                    impl_.trait_.is_none()
+                        && matches!(&impl_.for_, Type::ResolvedPath(path) if path.id == texture)
+                }
+                _ => false,
+            })
+            .unwrap()
+            .0
+            .clone();
+        let ItemEnum::Impl(impl_) = &krate.index[&impl_id].inner else {
+            unreachable!()
+        };
+        let mut constant = krate.index[&impl_.items[0]].clone();
+        constant.id = Id("max_size".to_string());
+        constant.name = Some("MAX_SIZE".to_string());
+        constant.inner = ItemEnum::AssocConst {
+            type_: Type::Primitive("u32".to_string()),
+            default: Some("16384".to_string()),
+        };
+        let ItemEnum::Impl(impl_) = &mut krate.index.get_mut(&impl_id).unwrap().inner else {
+            unreachable!()
+        };
+        impl_.items.push(constant.id.clone());
+        krate.index.insert(constant.id.clone(), constant);
+
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        process_module(&mut ctx, &query.root(), &["macroquad".to_string()], None);
+        let texture = ctx
+            .interfaces
+            .iter()
+            .find(|interface| interface.name == "texture")
+            .unwrap();
+        assert!(texture
+            .body
+            .contains("    width: func() -> f32;\n    max-size: static func() -> u32;\n"));
+        assert!(ctx.host.print().to_string().contains(
+            "    fn max_size(&mut self) -> wasmtime::Result<u32> {\n        Ok(macroquad::texture::Texture2D::MAX_SIZE)\n    }"
+        ));
+    }
+
+    #[test]
+    fn qualified_path() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        // `fn poll(..) -> Poll<<Self as Future>::Output>` in `impl Future for TimerDelayFuture`
+        let impl_ = query
+            .all_trait_impls()
+            .find(|impl_| {
+                impl_.trait_().is_some_and(|trait_| trait_.is(FUTURE_PATH))
+                    && matches!(
+                        impl_.for_().type_kind(),
+                        query::TypeKind::ResolvedPath(path) if path.name() == "TimerDelayFuture"
+                    )
+            })
+            .unwrap();
+        let (poll, _) = impl_
+            .items()
+            .find(|(_, item)| item.name.as_deref() == Some("poll"))
+            .unwrap();
+        let function = query
+            .all_functions()
+            .find(|function| function.data.id == *poll)
+            .unwrap();
+        let output = function.output().unwrap().type_args().next().unwrap();
+        assert!(wit_type(&ctx, &output).is_err());
+
+        let query::TypeKind::ResolvedPath(self_type) = impl_.for_().type_kind() else {
+            unreachable!()
+        };
+        ctx.self_type = Some(self_type.id().clone());
+        // `Output = Option<()>`, and `()` is no result rather than `tuple<>`
+        assert_eq!(
+            wit_type(&ctx, &output).err().unwrap(),
+            "`()` within another type"
+        );
+        let query::TypeKind::QualifiedPath(qpath) = output.type_kind() else {
+            unreachable!()
+        };
+        let resolved = qpath.resolve(ctx.self_type.as_ref()).unwrap();
+        let unit = resolved.type_args().next().unwrap();
+        assert!(
+            matches!(unit.type_kind(), query::TypeKind::Tuple(types) if types.types().next().is_none())
+        );
+    }
+
+    #[test]
+    fn operator_functions() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let impl_ = query
+            .all_trait_impls()
+            .find(|impl_| {
+                impl_
+                    .trait_()
+                    .is_some_and(|trait_| trait_.is(&["core", "cmp", "PartialEq"]))
+                    && matches!(
+                        impl_.for_().type_kind(),
+                        query::TypeKind::ResolvedPath(path) if path.name() == "Color"
+                    )
+            })
+            .unwrap();
+        let query::TypeKind::ResolvedPath(self_type) = impl_.for_().type_kind() else {
+            unreachable!()
+        };
+        ctx.self_type = Some(self_type.id().clone());
+        let operator = operator(&ctx, &impl_, "eq", "eq").unwrap();
+        assert_eq!(
+            operator.wit.print().to_string(),
+            "color-eq: func(a: color, b: color) -> bool;"
+        );
+
+        ctx.host
+            .operator(&ctx.paths, "macroquad::color::Color", "eq", &operator);
+        let host = ctx.host.print().to_string();
+        assert!(host.contains("let b: macroquad::color::Color = b.into();\n        Ok(a == b)\n"));
+
+        // `impl PartialEq for Texture2D`
+        let texture = path_id(&krate, &["macroquad", "texture", "Texture2D"]);
+        ctx.resources.insert(texture.clone());
+        ctx.self_type = Some(texture.clone());
+        let impl_ = query
+            .all_trait_impls()
+            .find(|impl_| {
+                impl_
+                    .trait_()
+                    .is_some_and(|trait_| trait_.is(&["core", "cmp", "PartialEq"]))
+                    && matches!(
+                        impl_.for_().type_kind(),
+                        query::TypeKind::ResolvedPath(path) if *path.id() == texture
+                    )
+            })
+            .unwrap();
+        assert_eq!(
+            super::operator(&ctx, &impl_, "eq", "eq").err().unwrap(),
+            "resource operands or result, which the operator would consume"
+        );
+    }
+
+    #[test]
+    fn keyword_derived_names() {
+        let mut krate = fixture();
+        // call `Color` `Type` and `DrawMode` `Future`, which are `%type` and
+        // `%future` in WIT
+        let color = local_id(&krate, "Color");
+        krate.index.get_mut(&color).unwrap().name = Some("Type".to_string());
+        let draw_mode = local_id(&krate, "DrawMode");
+        krate.index.get_mut(&draw_mode).unwrap().name = Some("Future".to_string());
+
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let impl_ = query
+            .all_trait_impls()
+            .find(|impl_| {
+                impl_
+                    .trait_()
+                    .is_some_and(|trait_| trait_.is(&["core", "cmp", "PartialEq"]))
+                    && matches!(
+                        impl_.for_().type_kind(),
+                        query::TypeKind::ResolvedPath(path) if *path.id() == color
+                    )
+            })
+            .unwrap();
+        ctx.self_type = Some(color.clone());
+        let operator = operator(&ctx, &impl_, "eq", "eq").unwrap();
+        assert_eq!(
+            operator.wit.print().to_string(),
+            "type-eq: func(a: %type, b: %type) -> bool;"
+        );
+
+        ctx.config =
+            serde_json::from_str(r#"{ "defaults": { "helpers": ["debug-string"] } }"#).unwrap();
+        let struct_ = query
+            .all_structs()
+            .find(|struct_| struct_.data.id == color)
+            .unwrap();
+        let ty = WitType::builtin(WitTypeKind::Named(names::ident("Type")));
+        let helpers = helpers(&ctx, "macroquad::color::Type", &ty, struct_.trait_impls());
+        assert_eq!(helpers[0].1.name, "type-debug-string");
+
+        let enum_ = query
+            .all_enums()
+            .find(|enum_| enum_.data.id == draw_mode)
+            .unwrap();
+        let (wit, _) = c_like_enum(&enum_, "Future", "macroquad::models::Future");
+        let (to, from) = discriminant_functions(&enum_, &wit).unwrap();
+        assert_eq!(to.name, "future-to-discriminant");
+        assert_eq!(from.name, "future-from-discriminant");
+        assert_eq!(wit_ident("%type"), "%type");
+    }
+
+    #[test]
+    fn std_trait_helpers() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let color = query
+            .all_structs()
+            .find(|struct_| struct_.name() == "Color")
+            .unwrap();
+        let ty = WitType::builtin(WitTypeKind::Named("color".to_string()));
+        let rust_path = "macroquad::color::Color";
+        assert!(helpers(&ctx, rust_path, &ty, color.trait_impls()).is_empty());
+
+        ctx.config = serde_json::from_str(
+            r#"{ "items": { "macroquad::color::Color": { "helpers": ["debug-string", "default", "clone", "parse"] } } }"#,
+        )
+        .unwrap();
+        let helpers = helpers(&ctx, rust_path, &ty, color.trait_impls())
+            .into_iter()
+            .map(|(_, wit)| wit.print().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            helpers,
+            [
+                "color-debug-string: func(value: color) -> string;",
+                "color-default: func() -> color;",
+            ]
+        );
+
+        // resources are borrowed from the resource table and pushed into it
+        let resource = WitType::builtin(WitTypeKind::Resource("color".to_string()));
+        for (helper, wit) in super::helpers(&ctx, rust_path, &resource, color.trait_impls()) {
+            ctx.host.helper(rust_path, helper, &wit);
+        }
+        let host = ctx.host.print().to_string();
+        assert!(host.contains(
+            "    fn color_clone(&mut self, value: wasmtime::component::Resource<wit::Color>) -> wasmtime::Result<wasmtime::component::Resource<wit::Color>> {\n        let value: &macroquad::color::Color = self.table.get(&value)?;\n        let value = value.clone();\n        Ok(self.table.push(value)?)\n    }"
+        ));
+        assert!(host.contains("Ok(self.table.push(macroquad::color::Color::default())?)"));
+    }
+
+    #[test]
+    fn conversion_functions() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let ctx = context(&query);
+        let mut conversions = query
+            .all_trait_impls()
+            .filter(|impl_| !impl_.is_synthetic() && !impl_.is_blanket())
+            .filter(|impl_| impl_.trait_().is_some_and(|trait_| trait_.is(INTO_PATH)))
+            .filter_map(|impl_| conversion(&ctx, &impl_, "into").ok().flatten())
+            .map(|conversion| conversion.wit.print().to_string())
+            .filter(|wit| wit.starts_with("color-"))
+            .collect::<Vec<_>>();
+        conversions.sort();
+        assert_eq!(
+            conversions,
+            [
+                "color-into-list-f32: func(value: color) -> list<f32>;",
+                "color-into-list-u8: func(value: color) -> list<u8>;",
+            ]
+        );
+
+        // `impl Into<Camera2D> for &mouse::Camera`
+        let mut ctx = ctx;
+        let camera2d = path_id(&krate, &["macroquad", "camera", "Camera2D"]);
+        let camera = path_id(
+            &krate,
+            &["macroquad", "experimental", "camera", "mouse", "Camera"],
+        );
+        ctx.resources.extend([camera2d.clone(), camera.clone()]);
+        let impl_ = query
+            .all_trait_impls()
+            .find(|impl_| {
+                impl_.trait_().is_some_and(|trait_| trait_.is(INTO_PATH))
+                    && impl_.for_().mentions(&camera)
+                    && impl_.trait_args().any(|arg| arg.mentions(&camera2d))
+            })
+            .unwrap();
+        assert_eq!(
+            conversion(&ctx, &impl_, "into").err().unwrap(),
+            "converts a resource, which conversions take by value"
+        );
+    }
+
+    #[test]
+    fn bitflags_to_flags() {
+        let mut krate = fixture();
+        // macroquad has no `bitflags!`, so give `Color` a flag constant
+        let (color_id, impl_id) = krate
+            .index
+            .values()
+            .find_map(|item| {
+                let struct_ = item.inner.as_struct()?;
+                (item.name.as_deref() == Some("Color") && item.crate_id == 0).then(|| {
+                    let impl_ = struct_.impls.iter().find(|id| {
+                        matches!(
+                            &krate.index[id].inner,
+                            ItemEnum::Impl(Impl { trait_: None, .. })
+                        )
+                    });
+                    (item.id.clone(), impl_.unwrap().clone())
+                })
+            })
+            .unwrap();
+        let constant = Id("flag-red".to_string());
+        krate.index.insert(
+            constant.clone(),
+            Item {
+                id: constant.clone(),
+                crate_id: 0,
+                name: Some("RED_BIT".to_string()),
+                span: None,
+                visibility: Visibility::Public,
+                docs: None,
+                links: HashMap::new(),
+                attrs: Vec::new(),
+                deprecation: None,
+                inner: ItemEnum::AssocConst {
+                    type_: Type::Generic("Self".to_string()),
+                    default: Some("Self(1)".to_string()),
+                },
+            },
+        );
+        let ItemEnum::Impl(impl_) = &mut krate.index.get_mut(&impl_id).unwrap().inner else {
+            unreachable!()
+        };
+        impl_.items.push(constant);
+
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let color = query
+            .all_structs()
+            .find(|struct_| struct_.data.id == color_id)
+            .unwrap();
+        let rust_path = "macroquad::color::Color";
+        // no `BitOr` impl
+        assert_eq!(flags(&ctx, &color, rust_path), None);
+
+        ctx.config = serde_json::from_str(
+            r#"{ "items": { "macroquad::color::Color": { "flags": true } } }"#,
+        )
+        .unwrap();
+        let consts = flags(&ctx, &color, rust_path).unwrap();
+        assert_eq!(consts, ["RED_BIT"]);
+        let wit = Flags {
+            name: "color".to_string(),
+            flags: consts
+                .iter()
+                .map(|name| name.to_case(Case::Kebab))
+                .collect(),
+        };
+        assert_eq!(
+            WitTypeKind::Flags(wit.clone()).print().to_string(),
+            "flags color {red-bit}"
+        );
+        ctx.host.flags_conversion(rust_path, &wit, &consts);
+        let host = ctx.host.print_conversions().to_string();
+        assert!(host.contains(
+            "if flags.contains(macroquad::color::Color::RED_BIT) { wit |= Self::RED_BIT; }"
+        ));
+    }
+
+    #[test]
+    fn c_like_enum_discriminants() {
+        let mut krate = fixture();
+        // macroquad has no explicit discriminants, so give `DrawMode` some
+        let draw_mode = local_id(&krate, "DrawMode");
+        let draw_mode = krate.index.get_mut(&draw_mode).unwrap();
+        draw_mode.attrs.push("#[repr(u8)]".to_string());
+        let ItemEnum::Enum(enum_) = &draw_mode.inner else {
+            unreachable!()
+        };
+        let lines = enum_.variants[1].clone();
+        let ItemEnum::Variant(variant) = &mut krate.index.get_mut(&lines).unwrap().inner else {
+            unreachable!()
+        };
+        variant.discriminant = Some(rustdoc_types::Discriminant {
+            expr: "4".to_string(),
+            value: "4".to_string(),
+        });
+
+        let query = query::CrateQuery::new(&krate);
+        let enum_ = query
+            .all_enums()
+            .find(|enum_| enum_.name() == "DrawMode" && enum_.data.crate_id == 0)
+            .unwrap();
+        assert_eq!(enum_.repr(), Some("u8"));
+        let (wit, arms) = c_like_enum(&enum_, "DrawMode", "macroquad::models::DrawMode");
+        assert_eq!(
+            wit.kind.print().to_string(),
+            "enum draw-mode {\n    triangles,\n    /// `Lines = 4`\n    lines,\n}"
+        );
+        assert_eq!(arms[1].0, "macroquad::models::DrawMode::Lines");
+
+        let (to, from) = discriminant_functions(&enum_, &wit).unwrap();
+        assert_eq!(
+            to.print().to_string(),
+            "draw-mode-to-discriminant: func(value: draw-mode) -> u8;"
+        );
+        assert_eq!(
+            from.print().to_string(),
+            "draw-mode-from-discriminant: func(discriminant: u8) -> option<draw-mode>;"
+        );
+
+        let draw_mode = krate.index.get_mut(&local_id(&krate, "DrawMode")).unwrap();
+        draw_mode.attrs = vec!["#[repr(u128)]".to_string()];
+        let query = query::CrateQuery::new(&krate);
+        let enum_ = query
+            .all_enums()
+            .find(|enum_| enum_.name() == "DrawMode" && enum_.data.crate_id == 0)
+            .unwrap();
+        assert_eq!(
+            discriminant_functions(&enum_, &wit).err(),
+            Some("`#[repr(u128)]`, which has no WIT integer type".to_string())
+        );
+    }
+
+    #[test]
+    fn data_enum_variant() {
+        let mut krate = fixture();
+        // `Vec2` is a foreign type, so make the positions of `Drag` arrays
+        let ItemEnum::Enum(drag) = &krate.index[&local_id(&krate, "Drag")].inner else {
+            unreachable!()
+        };
+        let fields = drag
+            .variants
+            .iter()
+            .flat_map(|variant| match &krate.index[variant].inner {
+                ItemEnum::Variant(rustdoc_types::Variant {
+                    kind: rustdoc_types::VariantKind::Tuple(fields),
+                    ..
+                }) => fields.iter().flatten().take(1).cloned().collect(),
+                _ => Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        for field in fields {
+            krate.index.get_mut(&field).unwrap().inner = ItemEnum::StructField(Type::Array {
+                type_: Box::new(Type::Primitive("f32".to_string())),
+                len: "2".to_string(),
+            });
+        }
+
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let drag = query
+            .all_enums()
+            .find(|enum_| enum_.name() == "Drag")
+            .unwrap();
+        let (wit, cases) = data_variant(&ctx, &drag, "Drag").unwrap();
+        assert_eq!(
+            wit.kind.print().to_string(),
+            "variant drag {no, dragging(tuple<list<f32>, option<u64>>), dropped(tuple<list<f32>, option<u64>>)}"
+        );
+        let WitTypeKind::Variant(variant) = &wit.kind else {
+            unreachable!()
+        };
+        ctx.host
+            .variant_conversion("macroquad::ui::Drag", variant, &cases);
+        let conversions = ctx.host.print_conversions().to_string();
+        assert!(conversions.contains(
+            "macroquad::ui::Drag::Dragging(t0, t1) => Self::Dragging((t0.into_iter().map(Into::into).collect(), t1.map(Into::into))),"
+        ));
+        assert!(conversions.contains("impl TryFrom<wit::Drag> for macroquad::ui::Drag {"));
+        assert!(conversions.contains("wit::Drag::No => Ok(macroquad::ui::Drag::No),"));
+    }
+
+    #[test]
+    fn non_exhaustive_conversions() {
+        let mut krate = fixture();
+        for item in krate.index.values_mut() {
+            if item.crate_id == 0 && matches!(item.name.as_deref(), Some("DrawMode" | "Color")) {
+                item.attrs.push("#[non_exhaustive]".to_string());
+            }
+        }
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+
+        let enum_ = query
+            .all_enums()
+            .find(|enum_| enum_.name() == "DrawMode" && enum_.data.crate_id == 0)
+            .unwrap();
+        let rust_path = "macroquad::models::DrawMode";
+        let (wit, arms) = c_like_enum(&enum_, "DrawMode", rust_path);
+        assert_eq!(
+            wit.kind.print().to_string(),
+            "enum draw-mode {triangles, lines, unknown}"
+        );
+        let WitTypeKind::Enum(wit_enum) = &wit.kind else {
+            unreachable!()
+        };
+        ctx.host.enum_conversion(rust_path, wit_enum, &arms);
+        // the `unknown` case traps
+        let (to, from) = discriminant_functions(&enum_, &wit).unwrap();
+        ctx.host
+            .discriminant_functions(rust_path, &to, &from, &arms);
+        assert!(ctx
+            .host
+            .print()
+            .to_string()
+            .contains("let value: macroquad::models::DrawMode = value.try_into()?;\n"));
+
+        let color = query
+            .all_structs()
+            .find(|struct_| struct_.name() == "Color")
+            .unwrap();
+        let StructItemKind::StructPlain(plain) = color.struct_kind() else {
+            unreachable!()
+        };
+        let fields = plain.fields().collect::<Vec<_>>();
+        let record = Record {
+            name: "color".to_string(),
+            fields: fields
+                .iter()
+                .map(|(name, _)| (name.to_string(), WitType::builtin(WitTypeKind::Float32)))
+                .collect(),
+        };
+        ctx.host
+            .record_conversion("macroquad::color::Color", &record, &fields, true);
+
+        let host = ctx.host.print_conversions().to_string();
+        assert!(host.contains("#[allow(unreachable_patterns)]\n            _ => Self::Unknown,"));
+        assert!(host.contains(
+            "impl TryFrom<wit::DrawMode> for macroquad::models::DrawMode {\n    type Error = ConversionError;\n"
+        ));
+        assert!(host.contains(
+            "wit::DrawMode::Unknown => Err(ConversionError::Unknown { type_: \"macroquad::models::DrawMode\" }),"
+        ));
+        assert!(host.contains("impl From<macroquad::color::Color> for wit::Color"));
+        assert!(!host.contains("impl From<wit::Color> for macroquad::color::Color"));
+
+        // so functions can't take them
+        let clear_background = query
+            .all_functions()
+            .find(|function| function.name() == "clear_background")
+            .unwrap();
+        assert_eq!(
+            wit_function(&ctx, &clear_background).err().as_deref(),
+            Some(
+                "parameter `color`: `Color` is `#[non_exhaustive]`, so it can't be built from WIT"
+            )
+        );
+    }
+
+    #[test]
+    fn deprecated_and_hidden_items() {
+        let mut krate = fixture();
+        for item in krate.index.values_mut() {
+            match item.name.as_deref() {
+                Some("clear_background") => {
+                    item.deprecation = Some(rustdoc_types::Deprecation {
+                        since: Some("0.4.0".to_string()),
+                        note: Some("use `Camera`".to_string()),
+                    })
+                }
+                Some("screen_width") => item.attrs.push("#[doc(hidden)]".to_string()),
+                Some("experimental") if item.inner.as_module().is_some() => {
+                    item.attrs.push("#[doc(hidden)]".to_string())
+                }
+                _ => {}
+            }
+        }
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let function = |name: &str| {
+            query
+                .all_functions()
+                .find(|function| function.name() == name)
+                .unwrap()
+        };
+        let deprecated = function("clear_background");
+        let rust_path = "macroquad::window::clear_background";
+        assert_eq!(
+            annotation(&ctx, &function("screen_width"), rust_path),
+            Err("doc(hidden)")
+        );
+        assert_eq!(
+            annotation(&ctx, &function("screen_height"), rust_path),
+            Ok(None)
+        );
+        assert_eq!(
+            annotation(&ctx, &deprecated, rust_path),
+            Ok(Some("/// Deprecated since 0.4.0: use `Camera`".to_string()))
+        );
+
+        ctx.config =
+            serde_json::from_str(r#"{ "defaults": { "deprecated": "annotate" } }"#).unwrap();
+        // without a gate, there's only the note
+        assert_eq!(
+            annotation(&ctx, &deprecated, rust_path),
+            Ok(Some("/// Deprecated since 0.4.0: use `Camera`".to_string()))
+        );
+        ctx.config = serde_json::from_str(
+            r#"{ "defaults": { "deprecated": "annotate", "since": "0.3.0" } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            annotation(&ctx, &deprecated, rust_path),
+            Ok(Some(
+                "/// Deprecated since 0.4.0: use `Camera`\n@since(version = 0.3.0)\n@deprecated(version = 0.4.0)"
+                    .to_string()
+            ))
+        );
+        ctx.config = serde_json::from_str(r#"{ "defaults": { "deprecated": "skip" } }"#).unwrap();
+        assert_eq!(annotation(&ctx, &deprecated, rust_path), Err("deprecated"));
+
+        // skipped modules are reported in their parent's interface
+        process_module(&mut ctx, &query.root(), &["macroquad".to_string()], None);
+        let root = &ctx.interfaces[0];
+        assert_eq!(root.name, "macroquad-root");
+        assert!(root.body.contains("// experimental: SKIPPED: doc(hidden)"));
+        assert!(!ctx
+            .interfaces
+            .iter()
+            .any(|interface| interface.name.starts_with("experimental")));
+    }
+
+    #[test]
+    fn feature_gates() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let params = query
+            .all_structs()
+            .find(|struct_| struct_.name() == "PlaySoundParams")
+            .unwrap();
+        let function = query
+            .all_functions()
+            .find(|function| function.name() == "screen_width")
+            .unwrap();
+        let rust_path = "macroquad::window::screen_width";
+        assert_eq!(params.cfg_features(), ["audio"]);
+        assert_eq!(
+            annotation(&ctx, &params, "macroquad::audio::PlaySoundParams"),
+            Ok(Some("@unstable(feature = audio)".to_string()))
+        );
+        assert_eq!(annotation(&ctx, &function, rust_path), Ok(None));
+
+        ctx.config = serde_json::from_str(
+            r#"{ "defaults": { "since": "crate" }, "items": { "macroquad::window::screen_width": { "since": "0.3.0" } } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            annotation(&ctx, &function, rust_path),
+            Ok(Some("@since(version = 0.3.0)".to_string()))
+        );
+        assert_eq!(
+            annotation(&ctx, &function, "macroquad::window::screen_height"),
+            Ok(Some("@since(version = 0.4.4)".to_string()))
+        );
+    }
+
+    #[test]
+    fn wit_names() {
+        assert_eq!(names::ident("Texture2D"), "texture2-d");
+        assert_eq!(names::ident("draw_line_3d"), "draw-line3-d");
+        assert_eq!(names::ident("r#type"), "%type");
+        assert_eq!(names::ident("Option"), "%option");
+        assert_eq!(names::ident("ErrorContext"), "%error-context");
+        assert_eq!(names::ident("_3d"), "n3-d");
+        assert_eq!(host::function_name("%type"), "type_");
+        assert_eq!(host::bindgen_name("texture2-d"), "Texture2D");
+        // `heck` keeps digits in their word, unlike `convert_case`
+        assert_eq!(host::function_name("from-rgba8"), "from_rgba8");
+        assert_eq!(host::function_name("texture2-d-eq"), "texture2_d_eq");
+        assert_eq!(
+            host::function_name("color-into-list-u8"),
+            "color_into_list_u8"
+        );
+        assert_eq!(host::bindgen_name("iterator-u8"), "IteratorU8");
+
+        let mut scope = names::Scope::default();
+        assert_eq!(scope.declare("Vec2"), Ok("vec2".to_string()));
+        assert_eq!(scope.declare("Vec2"), Ok("vec2".to_string()));
+        assert_eq!(
+            scope.declare("VEC2"),
+            Err("`VEC2` and `Vec2` are both `vec2` in WIT".to_string())
+        );
+
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let mut scope = names::Scope::default();
+        let name = declare(
+            &mut ctx,
+            &mut scope,
+            "macroquad::texture::Texture2D",
+            "Texture2D",
+        );
+        assert_eq!(name.as_deref(), Ok("texture2-d"));
+        declare(&mut ctx, &mut scope, "macroquad::color::Color", "Color").unwrap();
+        assert!(ctx.host.print().to_string().contains(
+            "pub const WIT_NAMES: &[(&str, &str)] = &[\n    (\"macroquad::texture::Texture2D\", \"texture2-d\"),\n];"
+        ));
+    }
+
+    #[test]
+    fn resource_method_names() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let camera = query
+            .all_structs()
+            .find(|struct_| struct_.name() == "Camera2D")
+            .unwrap();
+        let rust_path = "macroquad::camera::Camera2D";
+        ctx.self_type = Some(camera.data.id.clone());
+        ctx.resources.insert(camera.data.id.clone());
+        ctx.config = serde_json::from_str(
+            r#"{ "items": { "macroquad::camera::Camera2D": { "method-names": { "Camera::depth_enabled": "has_depth" } } } }"#,
+        )
+        .unwrap();
+        // an inherent `viewport` would take the name from `Camera::viewport`
+        let (methods, _) = resource_methods(&ctx, &camera, rust_path, &["viewport".to_string()]);
+        let names = methods
+            .iter()
+            .map(|method| method.wit.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["from-display-rect", "has-depth", "camera-viewport"]);
+
+        ctx.host.resource_methods(rust_path, "camera2-d", &methods);
+        let host = ctx.host.print().to_string();
+        assert!(host.contains("pub type Camera2D = macroquad::camera::Camera2D;"));
+        assert!(host.contains(
+            "<macroquad::camera::Camera2D as macroquad::camera::Camera>::viewport(object)"
+        ));
+        // `Self` results are pushed into the resource table
+        assert!(host.contains(
+            "    fn from_display_rect(&mut self, rect: wit::Rect) -> wasmtime::Result<wasmtime::component::Resource<wit::Camera2D>> {\n        let value = macroquad::camera::Camera2D::from_display_rect(rect.into());\n        Ok(self.table.push(value)?)\n    }"
+        ));
+    }
+
+    #[test]
+    fn resource_handles() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        for path in [
+            ["macroquad", "texture", "Texture2D"],
+            ["macroquad", "text", "Font"],
+            ["macroquad", "ui", "Ui"],
+        ] {
+            ctx.resources.insert(path_id(&krate, &path));
+        }
+        ctx.resources.insert(local_id(&krate, "Skin"));
+        let function = |name: &str| {
+            query
+                .all_functions()
+                .find(|function| function.name() == name)
+                .unwrap()
+        };
+        ctx.host.set_interface("text");
+
+        // `draw_texture(texture: &Texture2D, ..)`
+        let draw_texture = function("draw_texture");
+        let wit = wit_function(&ctx, &draw_texture).unwrap();
+        assert_eq!(
+            wit.print().to_string(),
+            "draw-texture: func(texture: borrow<texture2-d>, x: f32, y: f32, color: color);"
+        );
+        ctx.host
+            .function("macroquad::texture::draw_texture", &draw_texture, &wit);
+        // `measure_text(text: &str, font: Option<&Font>, ..)`
+        let measure_text = function("measure_text");
+        let wit = wit_function(&ctx, &measure_text).unwrap();
+        assert_eq!(wit.params[1].1.kind.print_ref(), "option<borrow<font>>");
+        ctx.host
+            .function("macroquad::text::measure_text", &measure_text, &wit);
+        // `load_ttf_font_from_bytes(..) -> Result<Font, Error>`
+        let load_font = function("load_ttf_font_from_bytes");
+        let wit = wit_function(&ctx, &load_font).unwrap();
+        ctx.host.function(
+            "macroquad::text::load_ttf_font_from_bytes",
+            &load_font,
+            &wit,
+        );
+        let host = ctx.host.print().to_string();
+        assert!(host.contains(
+            "        let texture = self.table.get(&texture)?;\n        macroquad::texture::draw_texture(texture, x, y, color.into());"
+        ));
+        assert!(host.contains(
+            "        let font = match &font { Some(font) => Some(self.table.get(font)?), None => None };"
+        ));
+        assert!(host.contains(
+            "        Ok(match value { Ok(value) => Ok(self.table.push(value)?), Err(error) => Err(error.into()) })"
+        ));
+
+        // `Ui::push_skin(&mut self, skin: &Skin)` would borrow the table twice
+        ctx.self_type = Some(path_id(&krate, &["macroquad", "ui", "Ui"]));
+        assert_eq!(
+            wit_function(&ctx, &function("push_skin")).err().unwrap(),
+            "borrows a resource mutably along with another borrow"
+        );
+    }
+
+    #[test]
+    fn reexported_and_undefined_types() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let root = query.root();
+        find_types(&mut ctx, &root, "macroquad");
+        process_module(&mut ctx, &root, &["macroquad".to_string()], None);
+        let body = |name: &str| {
+            ctx.interfaces
+                .iter()
+                .find(|interface| interface.name == name)
+                .unwrap()
+                .body
+                .clone()
+        };
+        // `pub use rect::Rect` in `math`, and `Color` in `color` rather than
+        // `prelude`
+        assert!(body("math").contains("record rect {x: f32, y: f32, w: f32, h: f32}\n"));
+        let color = local_id(&krate, "Color");
+        assert_eq!(ctx.type_interfaces[&color].0, "color");
+        assert!(!body("prelude").contains("record color"));
+        // `type Id = u64`
+        assert!(body("ui").contains("focus-window: func(id: u64);\n"));
+        // `FrameFuture` is in a private module
+        assert!(body("window")
+            .contains("// next-frame: UNSUPPORTED: return type: `frame-future` isn't exported\n"));
+    }
+
+    #[test]
+    fn import_and_export_worlds() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        ctx.trait_interfaces.push("input-handler".to_string());
+        ctx.interfaces.push(ModuleInterface {
+            name: "macroquad-root".to_string(),
+            annotation: None,
+            body: String::new(),
+            referenced: Vec::new(),
+            uses: Vec::new(),
+        });
+        let includes = ["maxiquad:glam/glam-host".to_string()];
+
+        let host = worlds(&ctx, "macroquad", WorldKind::Import, &includes);
+        assert_eq!(host.len(), 1);
+        assert_eq!(
+            host[0].print().to_string(),
+            "world macroquad-host {\n    include maxiquad:glam/glam-host;\n    import macroquad-root;\n    export input-handler;\n}"
+        );
+
+        let combined = worlds(&ctx, "macroquad", WorldKind::Combined, &[])
+            .iter()
+            .map(|world| world.print().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            combined[1],
+            "world macroquad-guest {\n    import input-handler;\n    export macroquad-root;\n}"
+        );
+        assert_eq!(
+            combined[2],
+            "world macroquad {\n    include macroquad-host;\n    include macroquad-guest;\n}"
+        );
+    }
+
+    #[test]
+    fn module_interfaces() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let root = query.root();
+        process_module(&mut ctx, &root, &["macroquad".to_string()], None);
+        let wit = print_interfaces(&ctx);
+        assert!(!wit.contains("UNSUPPORTED: cyclic"));
+        assert!(wit.contains("interface color-colors {\n    use color.{color};\n"));
+        let position = |name: &str| wit.find(&format!("interface {name} {{")).unwrap();
+        assert!(position("texture") < position("material"));
+        assert!(position("color") < position("color-colors"));
+
+        // `a` and `b` use each other's types, and `c` uses `a`
+        let interface = |name: &str, referenced: &[&str]| ModuleInterface {
+            name: name.to_string(),
+            annotation: None,
+            body: String::new(),
+            referenced: referenced.iter().map(|id| Id(id.to_string())).collect(),
+            uses: Vec::new(),
+        };
+        ctx.interfaces = vec![
+            interface("c", &["x"]),
+            interface("b", &["x"]),
+            interface("a", &["y"]),
+        ];
+        ctx.type_interfaces = HashMap::from([
+            (Id("x".to_string()), ("a".to_string(), "x".to_string())),
+            (Id("y".to_string()), ("b".to_string(), "y".to_string())),
+        ]);
+        let wit = print_interfaces(&ctx);
+        assert!(wit.starts_with("// interfaces a, b: UNSUPPORTED: cyclic `use`s"));
+        assert!(wit.contains("interface a {\n    use b.{y};\n}\n"));
+    }
+
+    #[test]
+    fn sibling_interfaces() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let root = query.root();
+        process_module(&mut ctx, &root, &["macroquad".to_string()], None);
+        let wit = print_interfaces(&ctx);
+        // traits are interfaces next to the one of their module
+        assert!(!wit.contains("    interface "));
+        assert!(
+            wit.contains("interface ui-clipboard-object {\n    get: func() -> option<string>;\n")
+        );
+        assert!(wit.contains("interface ui-input-handler {\n    use ui.{key-code};\n"));
+        assert_eq!(
+            worlds(&ctx, "macroquad", WorldKind::Import, &[])[0].exports,
+            ["ui-clipboard-object", "ui-input-handler"]
+        );
+
+        // a `Host` impl per interface
+        let host = ctx.host.print().to_string();
+        assert!(host.contains("impl wit::texture::Host for HostState {"));
+        assert!(host.contains("impl wit::texture::HostTexture2D for HostState {"));
+        assert!(!host.contains("impl wit::Host for HostState"));
+    }
+
+    #[test]
+    fn type_definition_order() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let zone = query
+            .all_structs()
+            .find(|struct_| struct_.name() == "Zone")
+            .unwrap();
+        assert!(is_recursive(&zone));
+        let color = query
+            .all_structs()
+            .find(|struct_| struct_.name() == "Color")
+            .unwrap();
+        assert!(!is_recursive(&color));
+
+        // `b` refers to `a`, `c` and `d` to each other, and `e` to `c`
+        let mut definition = |id: &str, deps: &[&str]| {
+            ctx.type_interfaces
+                .insert(Id(id.to_string()), ("x".to_string(), id.to_string()));
+            Definition {
+                id: Id(id.to_string()),
+                text: format!("{id};"),
+                deps: deps.iter().map(|id| Id(id.to_string())).collect(),
+            }
+        };
+        let definitions = vec![
+            definition("b", &["a", "b"]),
+            definition("c", &["d"]),
+            definition("a", &[]),
+            definition("d", &["c"]),
+            definition("e", &["c"]),
+        ];
+        assert_eq!(
+            print_definitions(&ctx, definitions),
+            "// c, d: UNSUPPORTED: recursive types, which WIT can't define\na;b;c;d;e;"
+        );
+
+        // as are the payloads of variants
+        let payload = WitType {
+            kind: WitTypeKind::Named("a".to_string()),
+            functions: (),
+            source: Source::Local(Id("a".to_string())),
+        };
+        let variant = WitType::builtin(WitTypeKind::Variant(Variant {
+            name: "v".to_string(),
+            cases: vec![("x".to_string(), Some(payload))],
+        }));
+        let mut deps = Vec::new();
+        local_types(&variant, &mut deps);
+        assert_eq!(deps, [Id("a".to_string())]);
+    }
+
+    #[test]
+    fn recursive_struct_arena() {
+        let mut krate = fixture();
+        // give `Animation` a `children: Vec<Animation>` field
+        let animation = local_id(&krate, "Animation");
+        let child = Type::ResolvedPath(rustdoc_types::Path {
+            name: "Animation".to_string(),
+            id: animation.clone(),
+            args: None,
+        });
+        let children = generic("Vec", path_id(&krate, VEC_PATH), child);
+        push_field(&mut krate, &animation, "children", children);
+
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let animation = query
+            .all_structs()
+            .find(|struct_| struct_.name() == "Animation")
+            .unwrap();
+        assert!(is_recursive(&animation));
+        let (record, entry, fields) = arena(&ctx, &animation, "animation").unwrap();
+        assert_eq!(
+            WitTypeKind::Record(entry.clone()).print().to_string(),
+            "record animation-entry {name: string, row: u32, frames: u32, fps: u32, children: list<u32>}"
+        );
+        assert_eq!(
+            WitTypeKind::Record(record.clone()).print().to_string(),
+            "record animation {entries: list<animation-entry>}"
+        );
+        assert_eq!(fields[4].2, Some(SelfRef::Vec { boxed: false }));
+
+        let rust_path = "macroquad::experimental::animation::Animation";
+        ctx.host
+            .arena_conversion(rust_path, &record, &entry, &fields, false);
+        let host = ctx.host.print_conversions().to_string();
+        assert!(host.contains(
+            "children: value.children.into_iter().map(|child| push(child, entries)).collect(),"
+        ));
+        assert!(host.contains(&format!("impl TryFrom<wit::Animation> for {rust_path} {{")));
+        assert!(host.contains(
+            "values.get_mut(index as usize).and_then(Option::take).ok_or(ConversionError::Index { type_: \"macroquad::experimental::animation::Animation\", index })"
+        ));
+        assert!(host.contains(
+            "children: entry.children.into_iter().map(&mut take).collect::<Result<_, _>>()?,"
+        ));
+        assert!(host.contains("values.pop().flatten().ok_or(ConversionError::Empty {"));
+
+        // types containing the arena convert back with `TryFrom` too, e.g.
+        // `Vec<Animation>`, but not in the default mode, where it's a resource
+        let field = &fields[4].1;
+        assert!(!ctx.host.is_fallible(field));
+        ctx.config = serde_json::from_str(r#"{ "defaults": { "recursive": "arena" } }"#).unwrap();
+        ctx.host.set_arenas(arenas(&ctx, &query));
+        assert!(ctx.host.is_fallible(field));
+    }
+
+    #[test]
+    fn recursive_enum_unsupported() {
+        let mut krate = fixture();
+        // make the first field of `Drag::Dragging` a `Box<Drag>`
+        let drag = local_id(&krate, "Drag");
+        let ItemEnum::Enum(enum_) = &krate.index[&drag].inner else {
+            unreachable!()
+        };
+        let dragging = enum_.variants[1].clone();
+        let ItemEnum::Variant(rustdoc_types::Variant {
+            kind: rustdoc_types::VariantKind::Tuple(fields),
+            ..
+        }) = &krate.index[&dragging].inner
+        else {
+            unreachable!()
+        };
+        let field = fields[0].clone().unwrap();
+        let boxed = generic(
+            "Box",
+            path_id(&krate, BOX_PATH),
+            Type::ResolvedPath(rustdoc_types::Path {
+                name: "Drag".to_string(),
+                id: drag,
+                args: None,
+            }),
+        );
+        krate.index.get_mut(&field).unwrap().inner = ItemEnum::StructField(boxed);
+
+        let query = query::CrateQuery::new(&krate);
+        let ctx = context(&query);
+        let drag = query
+            .all_enums()
+            .find(|enum_| enum_.name() == "Drag")
+            .unwrap();
+        assert_eq!(
+            data_variant(&ctx, &drag, "Drag").err().unwrap(),
+            "`Dragging` contains the enum itself"
+        );
+    }
+
+    #[test]
+    fn guest_wrappers() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let root = query.root();
+        process_module(&mut ctx, &root, &["macroquad".to_string()], None);
+
+        let named = |name: &str| WitType::builtin(WitTypeKind::Named(name.to_string()));
+        let function = |name: &str, params: &[WitType], output: WitType| WitFunction {
+            name: name.to_string(),
+            params: params
+                .iter()
+                .map(|ty| ("value".to_string(), ty.clone()))
+                .collect(),
+            output: Some(output),
+            is_async: false,
+        };
+        let color = named("color");
+        let string = WitType::builtin(WitTypeKind::String);
+        ctx.guest.helper(
+            config::Helper::ToString,
+            &function(
+                "color-to-string",
+                std::slice::from_ref(&color),
+                string.clone(),
+            ),
+        );
+        ctx.guest.operator(
+            "Add",
+            "add",
+            &function("color-add", &[color.clone(), color.clone()], color.clone()),
+        );
+        ctx.guest.conversion(
+            &function(
+                "error-from-string",
+                std::slice::from_ref(&string),
+                named("error"),
+            ),
+            false,
+        );
+        let fallible = WitTypeKind::Result {
+            ok: Some(Box::new(named("error"))),
+            err: None,
+        };
+        ctx.guest.conversion(
+            &function(
+                "error-try-from-string",
+                &[string],
+                WitType::builtin(fallible),
+            ),
+            true,
+        );
+        let wrappers = ctx.guest.print().to_string();
+
+        assert!(wrappers.contains(
+            "pub mod color {\n    pub use crate::wit::{Color, hsl_to_rgb, rgb_to_hsl};\n\n    pub mod colors {\n        pub use crate::wit::{beige as BEIGE,"
+        ));
+        assert!(wrappers.contains(
+            "impl core::fmt::Display for crate::wit::Color {\n    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {\n        f.write_str(&crate::wit::color_to_string(*self))\n    }\n}"
+        ));
+        assert!(wrappers.contains(
+            "impl core::ops::Add<crate::wit::Color> for crate::wit::Color {\n    type Output = crate::wit::Color;\n"
+        ));
+        assert!(wrappers.contains("        crate::wit::error_from_string(&value)\n"));
+        // `From` implies `TryFrom`
+        assert!(!wrappers.contains("impl TryFrom"));
+
+        let files =
+            ctx.guest
+                .crate_files("maxiquad", "macroquad", "0.4.4", &[], "", "macroquad-host");
+        let lib = &files
+            .iter()
+            .find(|(path, _)| path.ends_with("lib.rs"))
+            .unwrap()
+            .1;
+        assert!(lib.contains("    world: \"macroquad-host\",\n"));
+        assert!(lib.contains("pub mod macroquad;"));
+    }
+
+    #[test]
+    fn guest_wit_parses() {
+        let krate = fixture();
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        ctx.async_ = true;
+        let root = query.root();
+        find_types(&mut ctx, &root, "macroquad");
+        let arenas = arenas(&ctx, &query);
+        ctx.host.set_arenas(arenas);
+        process_module(&mut ctx, &root, &["macroquad".to_string()], None);
+        process_crate(&mut ctx, &query);
+        let worlds = worlds(&ctx, "macroquad", WorldKind::Combined, &[]);
+        let wit = print_wit(&ctx, &worlds);
+        let user = user_interfaces(&ctx);
+        let interfaces = ctx
+            .interfaces
+            .iter()
+            .map(|interface| interface.name.clone())
+            .filter(|name| !user.contains(name))
+            .collect::<Vec<_>>();
+        let files = ctx.guest.crate_files(
+            "maxiquad",
+            "macroquad",
+            "0.4.4",
+            &interfaces,
+            &wit,
+            &worlds[0].name,
+        );
+        let (path, contents) = files
+            .iter()
+            .find(|(path, _)| path.extension().is_some_and(|ext| ext == "wit"))
+            .unwrap();
+        // the operators and conversions are in it too
+        assert!(contents.contains("    color-eq: func(a: color, b: color) -> bool;\n"));
+        assert!(contents.contains("    color-into-list-u8: func(value: color) -> list<u8>;\n"));
+        let group = wit_parser::UnresolvedPackageGroup::parse(path, contents).unwrap();
+        let mut resolve = wit_parser::Resolve {
+            all_features: true,
+            ..Default::default()
+        };
+        resolve.push_group(group).unwrap();
+        // every module's interface, and the host, guest and combined worlds
+        assert_eq!(resolve.interfaces.len(), ctx.interfaces.len());
+        let mut names = resolve
+            .worlds
+            .iter()
+            .map(|(_, world)| world.name.as_str())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["macroquad", "macroquad-guest", "macroquad-host"]);
+    }
+
+    #[test]
+    fn nested_conversions() {
+        let mut krate = fixture();
+        // give `TextDimensions` `corners: Option<Vec<[f32; 2]>>` and
+        // `pair: (String, Color)` fields
+        let dimensions = local_id(&krate, "TextDimensions");
+        let corners = generic(
+            "Option",
+            path_id(&krate, OPTION_PATH),
+            generic(
+                "Vec",
+                path_id(&krate, VEC_PATH),
+                Type::Array {
+                    type_: Box::new(Type::Primitive("f32".to_string())),
+                    len: "2".to_string(),
+                },
+            ),
+        );
+        let pair = Type::Tuple(vec![
+            Type::ResolvedPath(rustdoc_types::Path {
+                name: "String".to_string(),
+                id: path_id(&krate, STRING_PATH),
+                args: None,
+            }),
+            Type::ResolvedPath(rustdoc_types::Path {
+                name: "Color".to_string(),
+                id: local_id(&krate, "Color"),
+                args: None,
+            }),
+        ]);
+        push_field(&mut krate, &dimensions, "corners", corners);
+        push_field(&mut krate, &dimensions, "pair", pair);
+        // make `Ui::render` take `&Vec<DrawList>`, as `&mut` of a list isn't
+        // exported
+        for item in krate.index.values_mut() {
+            match (&item.name, &mut item.inner) {
+                (Some(name), ItemEnum::Function(function)) if name == "render" => {
+                    if let Some((_, Type::BorrowedRef { mutable, .. })) =
+                        function.decl.inputs.get_mut(1)
+                    {
+                        *mutable = false;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let query = query::CrateQuery::new(&krate);
+        let mut ctx = context(&query);
+        let root = query.root();
+        process_module(&mut ctx, &root, &["macroquad".to_string()], None);
+        let conversions = ctx.host.print_conversions().to_string();
+        assert!(conversions.contains(
+            "            corners: value.corners.map(|value| value.into_iter().map(|item| item.into_iter().map(Into::into).collect()).collect()),\n"
+        ));
+        assert!(conversions
+            .contains("            pair: { let (t0, t1,) = value.pair; (t0, t1.into(),) },\n"));
+        // a list might not fit the array
+        assert!(conversions.contains(
+            "impl TryFrom<wit::TextDimensions> for macroquad::text::TextDimensions {\n    type Error = ConversionError;\n"
+        ));
+        assert!(conversions.contains(
+            "            corners: match value.corners { Some(value) => Some(value.into_iter().map(|item| Ok(<[_; 2]>::try_from(item).map_err(|list: Vec<_>| ConversionError::Length { expected: 2, found: list.len() })?)).collect::<Result<Vec<_>, ConversionError>>()?), None => None },\n"
+        ));
+        assert!(conversions.contains("            width: value.width,\n"));
+        // host functions trap instead
+        let host = ctx.host.print().to_string();
+        assert!(host.contains(
+            "macroquad::ui::Ui::render(object, &draw_list.into_iter().map(|item| Ok(item.try_into()?)).collect::<Result<Vec<_>, conversions::ConversionError>>()?);\n        Ok(())\n"
+        ));
+    }
+}
@@ -0,0 +1,115 @@
+//! WIT identifiers for Rust names
+//!
+//! WIT identifiers are kebab-case words that each start with a letter, and
+//! those that are keywords have to be escaped with `%`, e.g. `%type`.
+
+use std::collections::HashMap;
+
+use convert_case::{Case, Casing};
+
+/// Words that are only identifiers when escaped with `%`
+const KEYWORDS: &[&str] = &[
+    "as",
+    "async",
+    "bool",
+    "borrow",
+    "char",
+    "constructor",
+    "enum",
+    "error-context",
+    "export",
+    "f32",
+    "f64",
+    "flags",
+    "float32",
+    "float64",
+    "from",
+    "func",
+    "future",
+    "import",
+    "include",
+    "interface",
+    "list",
+    "option",
+    "own",
+    "package",
+    "record",
+    "resource",
+    "result",
+    "s16",
+    "s32",
+    "s64",
+    "s8",
+    "static",
+    "stream",
+    "string",
+    "tuple",
+    "type",
+    "u16",
+    "u32",
+    "u64",
+    "u8",
+    "use",
+    "variant",
+    "with",
+    "world",
+];
+
+/// The WIT identifier for the Rust name `name`, e.g. `texture2-d` for
+/// `Texture2D` and `%type` for `r#type`
+pub fn ident(name: impl AsRef<str>) -> String {
+    let name = name.as_ref();
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    let kebab = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>()
+        .to_case(Case::Kebab);
+    let mut words: Vec<String> = Vec::new();
+    for word in kebab.split('-').filter(|word| !word.is_empty()) {
+        // words can't start with a digit, so they are joined to the previous one
+        match (
+            words.last_mut(),
+            word.starts_with(|c: char| c.is_ascii_digit()),
+        ) {
+            (Some(last), true) => last.push_str(word),
+            (None, true) => words.push(format!("n{word}")),
+            (_, false) => words.push(word.to_string()),
+        }
+    }
+    escaped(words.join("-"))
+}
+
+/// `ident`, escaped with `%` if it is a keyword
+pub fn escaped(ident: String) -> String {
+    if KEYWORDS.contains(&ident.as_str()) {
+        format!("%{ident}")
+    } else {
+        ident
+    }
+}
+
+/// The names declared in one WIT scope, e.g. the types and functions of an
+/// interface, to catch Rust names that map to the same identifier, like
+/// `Vec2` and `Vec_2`
+#[derive(Default)]
+pub struct Scope {
+    /// Rust names by identifier
+    names: HashMap<String, String>,
+}
+
+impl Scope {
+    /// The identifier for the Rust name `name`, or the Rust name it collides with
+    pub fn declare(&mut self, name: &str) -> Result<String, String> {
+        let ident = ident(name);
+        match self.names.get(&ident) {
+            Some(existing) if existing != name => Err(format!(
+                "`{name}` and `{existing}` are both `{ident}` in WIT"
+            )),
+            _ => {
+                self.names.insert(ident.clone(), name.to_string());
+                Ok(ident)
+            }
+        }
+    }
+}
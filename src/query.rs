@@ -591,7 +591,7 @@ impl<'c, T, K> Accessor<'c, T, K> {
         }
     }
 
-    pub fn krate(&self) -> &Crate {
+    pub fn krate(&self) -> &'c Crate {
         self.krate
     }
 
@@ -740,6 +740,47 @@ impl<'c, T> Item<'c, T> {
         self.data.attrs.as_slice()
     }
 
+    pub fn visibility(&self) -> &'c rustdoc_types::Visibility {
+        &self.data.visibility
+    }
+
+    pub fn deprecation(&self) -> Option<&'c rustdoc_types::Deprecation> {
+        self.data.deprecation.as_ref()
+    }
+
+    /// Whether the item is `#[doc(hidden)]`, so not part of the documented API
+    pub fn is_doc_hidden(&self) -> bool {
+        self.data.attrs.iter().any(|attr| attr == "#[doc(hidden)]")
+    }
+
+    /// The cargo features the item needs, from `#[doc(cfg(feature = "x"))]`
+    pub fn cfg_features(&self) -> Vec<&'c str> {
+        let cfgs = self.data.attrs.iter().filter_map(|attr| {
+            attr.strip_prefix("#[doc(cfg(")
+                .or_else(|| attr.strip_prefix("#[cfg("))
+        });
+        let mut features = Vec::new();
+        for mut cfg in cfgs {
+            while let Some((_, rest)) = cfg.split_once("feature = \"") {
+                let Some((feature, rest)) = rest.split_once('"') else {
+                    break;
+                };
+                features.push(feature);
+                cfg = rest;
+            }
+        }
+        features
+    }
+
+    /// Whether the item is `#[non_exhaustive]`, so other crates can't match it
+    /// exhaustively or construct it
+    pub fn is_non_exhaustive(&self) -> bool {
+        self.data
+            .attrs
+            .iter()
+            .any(|attr| attr == "#[non_exhaustive]")
+    }
+
     pub fn links(&self) -> impl Iterator<Item = (&'c str, &'c Id, &'c rustdoc_types::Item)> {
         // TODO: this silently drops items not in the index (may be in the paths)
         self.data.links.iter().filter_map(|(name, id)| {
@@ -985,8 +1026,23 @@ pub enum ModuleItemKind<'c> {
 // <import>
 
 impl<'c> Item<'c, &'c rustdoc_types::Import> {
-    pub fn name(&self) -> &str {
-        self.maybe_name().expect("import has a name")
+    /// The name the item is imported as (imports themselves are unnamed)
+    pub fn name(&self) -> &'c str {
+        self.inner.name.as_str()
+    }
+
+    /// The path as written, e.g. `error::Error` in `pub use error::Error;`
+    pub fn source(&self) -> &'c str {
+        self.inner.source.as_str()
+    }
+
+    pub fn is_glob(&self) -> bool {
+        self.inner.glob
+    }
+
+    /// The imported item, if it is in the index
+    pub fn target(&self) -> Option<(&'c rustdoc_types::Id, &'c rustdoc_types::Item)> {
+        self.inner.id.as_ref().and_then(|id| id.fetch(self.krate))
     }
 }
 
@@ -1005,7 +1061,37 @@ impl<'c> Item<'c, &'c rustdoc_types::Struct> {
         self.maybe_name().expect("struct has a name")
     }
 
-    pub fn struct_kind(&self) -> StructItemKind {
+    pub fn generics(&self) -> &'c rustdoc_types::Generics {
+        &self.inner.generics
+    }
+
+    pub fn inherent_impls(&self) -> impl Iterator<Item = Item<'c, &'c rustdoc_types::Impl>> + '_ {
+        self.inner
+            .impls
+            .fetch_many(self.krate)
+            .filter_map(|(_, item)| match &item.inner {
+                rustdoc_types::ItemEnum::Impl(impl_ @ rustdoc_types::Impl { trait_: None, .. }) => {
+                    Some(self.edge(item, impl_))
+                }
+                _ => None,
+            })
+    }
+
+    pub fn trait_impls(&self) -> impl Iterator<Item = Item<'c, &'c rustdoc_types::Impl>> + '_ {
+        self.inner
+            .impls
+            .fetch_many(self.krate)
+            .filter_map(|(_, item)| match &item.inner {
+                rustdoc_types::ItemEnum::Impl(
+                    impl_ @ rustdoc_types::Impl {
+                        trait_: Some(_), ..
+                    },
+                ) => Some(self.edge(item, impl_)),
+                _ => None,
+            })
+    }
+
+    pub fn struct_kind(&self) -> StructItemKind<'c> {
         match self.inner.kind {
             rustdoc_types::StructKind::Plain { .. } => {
                 StructItemKind::StructPlain(self.morph(Struct::new(self.inner)))
@@ -1050,7 +1136,9 @@ impl<'c, K> Item<'c, Struct<'c, K>> {
 }
 
 impl<'c> Item<'c, Struct<'c, StructPlain>> {
-    pub fn fields(&self) -> impl Iterator<Item = (&str, Item<'c, &rustdoc_types::Type>)> {
+    pub fn fields(
+        &self,
+    ) -> impl Iterator<Item = (&'c str, Item<'c, &'c rustdoc_types::Type>)> + '_ {
         let rustdoc_types::StructKind::Plain { fields, .. } = &self.inner.inner.kind else {
             unreachable!()
         };
@@ -1131,6 +1219,55 @@ impl<'c> Path<'c> {
         self.krate.paths.get(&self.inner.id)
     }
 
+    /// The path as written, e.g. `fmt::Display`
+    pub fn name(&self) -> &'c str {
+        self.inner.name.as_str()
+    }
+
+    pub fn id(&self) -> &'c Id {
+        &self.inner.id
+    }
+
+    /// Whether this path resolves to the item at the fully qualified `path`,
+    /// e.g. `["core", "fmt", "Display"]`
+    pub fn is(&self, path: &[&str]) -> bool {
+        self.summary().is_some_and(|summary| {
+            summary
+                .path
+                .iter()
+                .map(String::as_str)
+                .eq(path.iter().copied())
+        })
+    }
+
+    /// The trait this path resolves to, if it is in the index
+    pub fn trait_item(&self) -> Option<Item<'c, &'c rustdoc_types::Trait>> {
+        let (_, item) = self.inner.id.fetch(self.krate)?;
+        let trait_ = item.inner.as_trait()?;
+        Some(Item::new(self.krate, item, trait_))
+    }
+
+    /// The struct this path resolves to, if it is in the index
+    pub fn struct_item(&self) -> Option<Item<'c, &'c rustdoc_types::Struct>> {
+        let (_, item) = self.inner.id.fetch(self.krate)?;
+        let struct_ = item.inner.as_struct()?;
+        Some(Item::new(self.krate, item, struct_))
+    }
+
+    /// The enum this path resolves to, if it is in the index
+    pub fn enum_item(&self) -> Option<Item<'c, &'c rustdoc_types::Enum>> {
+        let (_, item) = self.inner.id.fetch(self.krate)?;
+        let enum_ = item.inner.as_enum()?;
+        Some(Item::new(self.krate, item, enum_))
+    }
+
+    /// The type alias this path resolves to, if it is in the index
+    pub fn type_alias_item(&self) -> Option<Item<'c, &'c rustdoc_types::TypeAlias>> {
+        let (_, item) = self.inner.id.fetch(self.krate)?;
+        let type_alias = item.inner.as_type_alias()?;
+        Some(Item::new(self.krate, item, type_alias))
+    }
+
     // this doesn't seem right
     pub fn item(&self) -> Option<Item<'_, Unknown>> {
         self.inner
@@ -1146,7 +1283,7 @@ impl<'c> Path<'c> {
 
 // TODO: a path can resolve to an Item or an ItemSummary
 impl<'c> Item<'c, &'c rustdoc_types::Type> {
-    pub fn type_kind(&self) -> TypeKind {
+    pub fn type_kind(&self) -> TypeKind<'c> {
         match self.inner {
             rustdoc_types::Type::ResolvedPath(inner) => {
                 // assert_eq!(self.id, &inner.id); // this fails, so morph is not correct
@@ -1196,6 +1333,37 @@ impl<'c> Item<'c, &'c rustdoc_types::Type> {
     }
 }
 
+impl<'c> Item<'c, &'c rustdoc_types::Type> {
+    /// Whether the type refers to the item `id`, e.g. `Vec<Box<Node>>` to `Node`
+    pub fn mentions(&self, id: &Id) -> bool {
+        match self.type_kind() {
+            TypeKind::ResolvedPath(path) => {
+                path.id() == id || self.type_args().any(|arg| arg.mentions(id))
+            }
+            TypeKind::Tuple(types) => types.types().any(|ty| ty.mentions(id)),
+            TypeKind::Slice(ty) => ty.mentions(id),
+            TypeKind::Array(array) => array.type_().mentions(id),
+            TypeKind::BorrowedRef(borrowed) => borrowed.type_().mentions(id),
+            _ => false,
+        }
+    }
+
+    /// The type arguments of a resolved path, e.g. `T` in `Option<T>`
+    pub fn type_args(&self) -> impl Iterator<Item = Item<'c, &'c rustdoc_types::Type>> + '_ {
+        let args = match self.inner {
+            rustdoc_types::Type::ResolvedPath(path) => match path.args.as_deref() {
+                Some(rustdoc_types::GenericArgs::AngleBracketed { args, .. }) => args.as_slice(),
+                _ => &[],
+            },
+            _ => &[],
+        };
+        args.iter().filter_map(|arg| match arg {
+            rustdoc_types::GenericArg::Type(ty) => Some(self.morph(ty)),
+            _ => None,
+        })
+    }
+}
+
 pub enum TypeKind<'c> {
     /// Structs, enums, and unions
     ResolvedPath(Path<'c>),
@@ -1242,6 +1410,189 @@ pub struct BorrowedRef<'c> {
     mutable: bool,
     type_: &'c rustdoc_types::Type,
 }
+impl<'c> Item<'c, &'c rustdoc_types::DynTrait> {
+    /// The traits of the object, e.g. `Trait` and `Send` in `dyn Trait + Send`
+    pub fn traits(&self) -> impl Iterator<Item = Path<'c>> + '_ {
+        self.inner
+            .traits
+            .iter()
+            .map(|poly| Path::new(self.krate, &poly.trait_))
+    }
+}
+
+impl<'c> Item<'c, &'c [rustdoc_types::Type]> {
+    pub fn types(&self) -> impl Iterator<Item = Item<'c, &'c rustdoc_types::Type>> + '_ {
+        self.inner.iter().map(|ty| self.morph(ty))
+    }
+}
+
+/// The signature of a closure bound, e.g. `(f32, &str)` and `bool` in
+/// `FnMut(f32, &str) -> bool`
+pub type FnSignature<'c> = (
+    Vec<Item<'c, &'c rustdoc_types::Type>>,
+    Option<Item<'c, &'c rustdoc_types::Type>>,
+);
+
+impl<'c> Item<'c, &'c [rustdoc_types::GenericBound]> {
+    /// The traits bounded by, e.g. `Iterator<Item = u8>` and `Send` in
+    /// `impl Iterator<Item = u8> + Send`
+    fn trait_bounds(&self) -> impl Iterator<Item = &'c rustdoc_types::Path> {
+        self.inner.iter().filter_map(|bound| match bound {
+            rustdoc_types::GenericBound::TraitBound { trait_, .. } => Some(trait_),
+            _ => None,
+        })
+    }
+
+    /// Whether the trait at the fully qualified `path` is one of the bounds
+    pub fn has_bound(&self, path: &[&str]) -> bool {
+        self.trait_bounds()
+            .any(|trait_| Path::new(self.krate, trait_).is(path))
+    }
+
+    /// The type arguments of the bound on the trait at `path`, e.g. `String`
+    /// in `impl Into<String>`
+    pub fn bound_args(&self, path: &[&str]) -> Vec<Item<'c, &'c rustdoc_types::Type>> {
+        self.trait_bounds()
+            .filter(|trait_| Path::new(self.krate, trait_).is(path))
+            .filter_map(|trait_| match trait_.args.as_deref() {
+                Some(rustdoc_types::GenericArgs::AngleBracketed { args, .. }) => Some(args),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|arg| match arg {
+                rustdoc_types::GenericArg::Type(ty) => Some(self.morph(ty)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The associated type `name` of the bound on the trait at `path`, e.g.
+    /// `u8` in `impl Iterator<Item = u8>`
+    pub fn binding(&self, path: &[&str], name: &str) -> Option<Item<'c, &'c rustdoc_types::Type>> {
+        self.trait_bounds()
+            .filter(|trait_| Path::new(self.krate, trait_).is(path))
+            .filter_map(|trait_| match trait_.args.as_deref() {
+                Some(rustdoc_types::GenericArgs::AngleBracketed { bindings, .. }) => Some(bindings),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|binding| match &binding.binding {
+                rustdoc_types::TypeBindingKind::Equality(rustdoc_types::Term::Type(ty))
+                    if binding.name == name =>
+                {
+                    Some(self.morph(ty))
+                }
+                _ => None,
+            })
+    }
+
+    /// The signature of the first `Fn`, `FnMut`, or `FnOnce` bound, if any
+    pub fn fn_signature(&self) -> Option<FnSignature<'c>> {
+        self.inner.iter().find_map(|bound| {
+            let rustdoc_types::GenericBound::TraitBound { trait_, .. } = bound else {
+                return None;
+            };
+            let path = Path::new(self.krate, trait_);
+            let is_fn = ["Fn", "FnMut", "FnOnce"]
+                .iter()
+                .any(|name| path.is(&["core", "ops", "function", name]));
+            match trait_.args.as_deref() {
+                Some(rustdoc_types::GenericArgs::Parenthesized { inputs, output }) if is_fn => {
+                    Some((
+                        inputs.iter().map(|ty| self.morph(ty)).collect(),
+                        output.as_ref().map(|ty| self.morph(ty)),
+                    ))
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+impl<'c> Item<'c, Array<'c>> {
+    pub fn type_(&self) -> Item<'c, &'c rustdoc_types::Type> {
+        self.morph(self.inner.type_)
+    }
+
+    pub fn len(&self) -> &'c str {
+        self.inner.len
+    }
+}
+
+impl<'c> Item<'c, QualifiedPath<'c>> {
+    /// e.g. `Output` in `<Self as Add>::Output`
+    pub fn name(&self) -> &'c str {
+        self.inner.name
+    }
+
+    pub fn args(&self) -> &'c rustdoc_types::GenericArgs {
+        self.inner.args
+    }
+
+    /// e.g. `Self` in `<Self as Add>::Output`
+    pub fn self_type(&self) -> Item<'c, &'c rustdoc_types::Type> {
+        self.morph(self.inner.self_type)
+    }
+
+    /// `None` for inherent associated types
+    pub fn trait_(&self) -> Option<Path<'c>> {
+        self.inner
+            .trait_
+            .map(|trait_| Path::new(self.krate, trait_))
+    }
+
+    /// The type the associated type is set to by the trait impl for the
+    /// self type, e.g. `Vec2` for `<Vec2 as Add>::Output`. A `Self` self type
+    /// is taken to be `self_id`.
+    ///
+    /// `Self::Output` doesn't say which `Add<Rhs>` it refers to, so this is
+    /// `None` if impls for different `Rhs` disagree.
+    pub fn resolve(&self, self_id: Option<&Id>) -> Option<Item<'c, &'c rustdoc_types::Type>> {
+        let self_id = match self.inner.self_type {
+            rustdoc_types::Type::ResolvedPath(path) => &path.id,
+            rustdoc_types::Type::Generic(generic) if generic == "Self" => self_id?,
+            _ => return None,
+        };
+        let trait_ = self.inner.trait_?;
+        let mut defaults = self.krate.index.values().filter_map(|item| {
+            let impl_ = item.inner.as_impl()?;
+            let impl_trait = impl_.trait_.as_ref()?;
+            let for_ = match &impl_.for_ {
+                rustdoc_types::Type::ResolvedPath(path) => &path.id,
+                _ => return None,
+            };
+            let args_match = trait_.args.is_none() || trait_.args == impl_trait.args;
+            if for_ != self_id || impl_trait.id != trait_.id || !args_match {
+                return None;
+            }
+            impl_
+                .items
+                .fetch_many(self.krate)
+                .find_map(|(_, item)| match &item.inner {
+                    rustdoc_types::ItemEnum::AssocType {
+                        default: Some(default),
+                        ..
+                    } if item.name.as_deref() == Some(self.inner.name) => Some(default),
+                    _ => None,
+                })
+        });
+        let default = defaults.next()?;
+        defaults
+            .all(|other| other == default)
+            .then(|| self.morph(default))
+    }
+}
+
+impl<'c> Item<'c, BorrowedRef<'c>> {
+    pub fn type_(&self) -> Item<'c, &'c rustdoc_types::Type> {
+        self.morph(self.inner.type_)
+    }
+
+    pub fn is_mutable(&self) -> bool {
+        self.inner.mutable
+    }
+}
+
 /// Associated types like `<Type as Trait>::Name` and `T::Item` where
 /// `T: Iterator` or inherent associated types like `Struct::Name`.
 pub struct QualifiedPath<'c> {
@@ -1261,6 +1612,19 @@ impl<'c> Item<'c, &'c rustdoc_types::Enum> {
         self.maybe_name().expect("enum has a name")
     }
 
+    /// The integer type of the discriminants, e.g. `u8` for `#[repr(u8)]`
+    pub fn repr(&self) -> Option<&'c str> {
+        const INTEGERS: &[&str] = &[
+            "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+        ];
+        self.data.attrs.iter().find_map(|attr| {
+            let args = attr.strip_prefix("#[repr(")?.strip_suffix(")]")?;
+            args.split(',')
+                .map(str::trim)
+                .find_map(|arg| INTEGERS.iter().copied().find(|int| *int == arg))
+        })
+    }
+
     pub fn inherent_impls(&self) -> impl Iterator<Item = Item<'c, &rustdoc_types::Impl>> {
         self.inner
             .impls
@@ -1287,7 +1651,7 @@ impl<'c> Item<'c, &'c rustdoc_types::Enum> {
             })
     }
 
-    pub fn variants(&self) -> impl Iterator<Item = Item<'c, &rustdoc_types::Variant>> {
+    pub fn variants(&self) -> impl Iterator<Item = Item<'c, &'c rustdoc_types::Variant>> + '_ {
         self.inner
             .variants
             .fetch_many(self.krate)
@@ -1311,7 +1675,12 @@ impl<'c> Item<'c, &'c rustdoc_types::Variant> {
         self.maybe_name().expect("variant has a name")
     }
 
-    pub fn variantkind(&self) -> VariantItemKind {
+    /// The explicit discriminant, e.g. `= 4`
+    pub fn discriminant(&self) -> Option<&'c rustdoc_types::Discriminant> {
+        self.inner.discriminant.as_ref()
+    }
+
+    pub fn variantkind(&self) -> VariantItemKind<'c> {
         match &self.inner.kind {
             rustdoc_types::VariantKind::Plain => {
                 VariantItemKind::Plain(self.morph(Variant::new(self.inner)))
@@ -1367,7 +1736,7 @@ impl<'c> Item<'c, Variant<'c, VariantTuple>> {
     // pub fn kind(&self) -> VariantKind {
     //     VariantKind::Tuple
     // }
-    pub fn fields(&self) -> impl Iterator<Item = Option<Item<'c, &rustdoc_types::Type>>> {
+    pub fn fields(&self) -> impl Iterator<Item = Option<Item<'c, &'c rustdoc_types::Type>>> + '_ {
         match &self.inner.inner.kind {
             rustdoc_types::VariantKind::Tuple(fields) => {
                 fields.fetch_many(self.krate).map(|pair| match pair {
@@ -1392,7 +1761,9 @@ impl<'c> Item<'c, Variant<'c, VariantStruct>> {
     // }
 
     /// An iterator over named fields of a struct
-    pub fn fields(&self) -> impl Iterator<Item = (&str, Item<'c, &rustdoc_types::Type>)> {
+    pub fn fields(
+        &self,
+    ) -> impl Iterator<Item = (&'c str, Item<'c, &'c rustdoc_types::Type>)> + '_ {
         match &self.inner.inner.kind {
             rustdoc_types::VariantKind::Struct { fields, .. } => fields
                 .fetch_many(self.krate)
@@ -1445,12 +1816,124 @@ impl<'c> Item<'c, &'c rustdoc_types::Function> {
     pub fn name(&self) -> &str {
         self.maybe_name().expect("function has a name")
     }
+
+    /// The parameters, including any `self` receiver
+    pub fn inputs(
+        &self,
+    ) -> impl Iterator<Item = (&'c str, Item<'c, &'c rustdoc_types::Type>)> + '_ {
+        self.inner
+            .decl
+            .inputs
+            .iter()
+            .map(|(name, ty)| (name.as_str(), self.morph(ty)))
+    }
+
+    pub fn output(&self) -> Option<Item<'c, &'c rustdoc_types::Type>> {
+        self.inner.decl.output.as_ref().map(|ty| self.morph(ty))
+    }
+
+    pub fn generics(&self) -> &'c rustdoc_types::Generics {
+        &self.inner.generics
+    }
+
+    pub fn header(&self) -> &'c rustdoc_types::Header {
+        &self.inner.header
+    }
+
+    /// The bounds of the `where` clauses on the generic `name`, e.g. `Sized`
+    /// in `where Self: Sized`
+    pub fn where_bounds<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = Item<'c, &'c [rustdoc_types::GenericBound]>> + 'a {
+        self.inner
+            .generics
+            .where_predicates
+            .iter()
+            .filter_map(move |predicate| match predicate {
+                rustdoc_types::WherePredicate::BoundPredicate {
+                    type_: rustdoc_types::Type::Generic(generic),
+                    bounds,
+                    ..
+                } if generic == name => Some(self.morph(bounds.as_slice())),
+                _ => None,
+            })
+    }
+
+    /// The type of the `self` receiver, if this is a method
+    pub fn receiver(&self) -> Option<Item<'c, &'c rustdoc_types::Type>> {
+        self.inputs()
+            .next()
+            .filter(|(name, _)| *name == "self")
+            .map(|(_, ty)| ty)
+    }
+
+    /// The parameters, excluding any `self` receiver
+    pub fn params(
+        &self,
+    ) -> impl Iterator<Item = (&'c str, Item<'c, &'c rustdoc_types::Type>)> + '_ {
+        self.inputs().skip(usize::from(self.receiver().is_some()))
+    }
+
+    /// Whether there is a default implementation, e.g. a provided trait method
+    pub fn has_body(&self) -> bool {
+        self.inner.has_body
+    }
+
+    /// The bounds on the type parameter `name`, both inline and in `where` clauses
+    pub fn generic_bounds(&self, name: &str) -> Vec<Item<'c, &'c [rustdoc_types::GenericBound]>> {
+        let generics = self.generics();
+        let params = generics
+            .params
+            .iter()
+            .filter_map(|param| match &param.kind {
+                rustdoc_types::GenericParamDefKind::Type { bounds, .. } if param.name == name => {
+                    Some(bounds.as_slice())
+                }
+                _ => None,
+            });
+        let predicates = generics
+            .where_predicates
+            .iter()
+            .filter_map(|predicate| match predicate {
+                rustdoc_types::WherePredicate::BoundPredicate {
+                    type_: rustdoc_types::Type::Generic(generic),
+                    bounds,
+                    ..
+                } if generic == name => Some(bounds.as_slice()),
+                _ => None,
+            });
+        params
+            .chain(predicates)
+            .map(|bounds| self.morph(bounds))
+            .collect()
+    }
 }
 
 impl<'c> Item<'c, &'c rustdoc_types::Trait> {
     pub fn name(&self) -> &str {
         self.maybe_name().expect("trait has a name")
     }
+
+    pub fn methods(&self) -> impl Iterator<Item = Item<'c, &'c rustdoc_types::Function>> + '_ {
+        self.inner
+            .items
+            .fetch_many(self.krate)
+            .filter_map(|(_, item)| item.inner.as_function().map(|inner| self.edge(item, inner)))
+    }
+
+    pub fn generics(&self) -> &'c rustdoc_types::Generics {
+        &self.inner.generics
+    }
+
+    /// Supertraits and lifetime bounds, e.g. `Clone + 'static` in `trait Foo: Clone + 'static`
+    pub fn bounds(&self) -> &'c [rustdoc_types::GenericBound] {
+        self.inner.bounds.as_slice()
+    }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.inner.is_unsafe
+    }
 }
 
 // </variant>
@@ -1463,7 +1946,92 @@ impl<'c> Item<'c, &'c rustdoc_types::TraitAlias> {}
 
 // <impl>
 
-impl<'c> Item<'c, &'c rustdoc_types::Impl> {}
+impl<'c> Item<'c, &'c rustdoc_types::Impl> {
+    /// The implemented trait, `None` for inherent impls
+    pub fn trait_(&self) -> Option<Path<'c>> {
+        self.inner
+            .trait_
+            .as_ref()
+            .map(|trait_| Path::new(self.krate, trait_))
+    }
+
+    /// The implementing type, e.g. `Bar` in `impl Foo for Bar {}`
+    pub fn for_(&self) -> Item<'c, &'c rustdoc_types::Type> {
+        self.morph(&self.inner.for_)
+    }
+
+    /// The type arguments of the implemented trait, e.g. `u32` in
+    /// `impl TryFrom<u32> for Key {}`
+    pub fn trait_args(&self) -> impl Iterator<Item = Item<'c, &'c rustdoc_types::Type>> + '_ {
+        let args = match self
+            .inner
+            .trait_
+            .as_ref()
+            .and_then(|trait_| trait_.args.as_deref())
+        {
+            Some(rustdoc_types::GenericArgs::AngleBracketed { args, .. }) => args.as_slice(),
+            _ => &[],
+        };
+        args.iter().filter_map(|arg| match arg {
+            rustdoc_types::GenericArg::Type(ty) => Some(self.morph(ty)),
+            _ => None,
+        })
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = (&'c rustdoc_types::Id, &'c rustdoc_types::Item)> {
+        self.inner.items.fetch_many(self.krate)
+    }
+
+    /// The functions defined in the impl, leaving out provided trait methods
+    /// it doesn't override
+    pub fn methods(&self) -> impl Iterator<Item = Item<'c, &'c rustdoc_types::Function>> + '_ {
+        self.items()
+            .filter_map(|(_, item)| item.inner.as_function().map(|inner| self.edge(item, inner)))
+    }
+
+    /// e.g. `const MAX: u32 = 255;`
+    pub fn assoc_consts(&self) -> impl Iterator<Item = Item<'c, AssocConst<'c>>> + '_ {
+        self.items().filter_map(|(_, item)| match &item.inner {
+            rustdoc_types::ItemEnum::AssocConst { type_, default } => Some(self.edge(
+                item,
+                AssocConst {
+                    type_,
+                    default: default.as_deref(),
+                },
+            )),
+            _ => None,
+        })
+    }
+
+    /// e.g. `type Output = Vec2;`
+    pub fn assoc_types(&self) -> impl Iterator<Item = Item<'c, AssocType<'c>>> + '_ {
+        self.items().filter_map(|(_, item)| match &item.inner {
+            rustdoc_types::ItemEnum::AssocType {
+                generics,
+                bounds,
+                default,
+            } => Some(self.edge(
+                item,
+                AssocType {
+                    generics,
+                    bounds: bounds.as_slice(),
+                    default: default.as_ref(),
+                },
+            )),
+            _ => None,
+        })
+    }
+
+    /// Auto trait impls like `Send` and `Sync`
+    pub fn is_synthetic(&self) -> bool {
+        self.inner.synthetic
+    }
+
+    /// Blanket impls like `impl<T> From<T> for T`
+    pub fn is_blanket(&self) -> bool {
+        self.inner.blanket_impl.is_some()
+    }
+}
 
 impl<'c, K> Item<'c, Impl<'c, K>> {
     pub fn items(&self) -> impl Iterator<Item = (&'c rustdoc_types::Id, &'c rustdoc_types::Item)> {
@@ -1495,7 +2063,16 @@ impl<'c, K> Impl<'c, K> {
 
 // <type-alias>
 
-impl<'c> Item<'c, &'c rustdoc_types::TypeAlias> {}
+impl<'c> Item<'c, &'c rustdoc_types::TypeAlias> {
+    /// The aliased type, e.g. `u64` for `type Id = u64`
+    pub fn type_(&self) -> Item<'c, &'c rustdoc_types::Type> {
+        self.morph(&self.inner.type_)
+    }
+
+    pub fn generics(&self) -> &'c rustdoc_types::Generics {
+        &self.inner.generics
+    }
+}
 
 // </type-alias>
 
@@ -1507,13 +2084,43 @@ impl<'c> Item<'c, &'c rustdoc_types::OpaqueTy> {}
 
 // <constant>
 
-impl<'c> Item<'c, &'c rustdoc_types::Constant> {}
+impl<'c> Item<'c, &'c rustdoc_types::Constant> {
+    pub fn name(&self) -> &str {
+        self.maybe_name().expect("constant has a name")
+    }
+
+    pub fn type_(&self) -> Item<'c, &'c rustdoc_types::Type> {
+        self.morph(&self.inner.type_)
+    }
+
+    /// The value, if rustdoc could evaluate it, e.g. `3.14159265358979323846264338327950288f64`
+    pub fn value(&self) -> Option<&'c str> {
+        self.inner.value.as_deref()
+    }
+
+    /// The expression as written, or `_` when it is too long to show
+    pub fn expr(&self) -> &'c str {
+        self.inner.expr.as_str()
+    }
+}
 
 // </constant>
 
 // <static>
 
-impl<'c> Item<'c, &'c rustdoc_types::Static> {}
+impl<'c> Item<'c, &'c rustdoc_types::Static> {
+    pub fn name(&self) -> &str {
+        self.maybe_name().expect("static has a name")
+    }
+
+    pub fn type_(&self) -> Item<'c, &'c rustdoc_types::Type> {
+        self.morph(&self.inner.type_)
+    }
+
+    pub fn is_mutable(&self) -> bool {
+        self.inner.mutable
+    }
+}
 
 // </static>
 
@@ -1537,13 +2144,44 @@ impl<'c> Item<'c, &'c rustdoc_types::Primitive> {}
 
 // <assoc-const>
 
-impl<'c> Item<'c, AssocConst<'c>> {}
+impl<'c> Item<'c, AssocConst<'c>> {
+    pub fn name(&self) -> &str {
+        self.maybe_name().expect("associated constant has a name")
+    }
+
+    pub fn type_(&self) -> Item<'c, &'c rustdoc_types::Type> {
+        self.morph(self.inner.type_)
+    }
+
+    /// The value as written, e.g. `5` in `const X: usize = 5;`
+    pub fn default(&self) -> Option<&'c str> {
+        self.inner.default
+    }
+}
 
 // </assoc-const>
 
 // <assoc-type>
 
-impl<'c> Item<'c, AssocType<'c>> {}
+impl<'c> Item<'c, AssocType<'c>> {
+    pub fn name(&self) -> &str {
+        self.maybe_name().expect("associated type has a name")
+    }
+
+    pub fn generics(&self) -> &'c rustdoc_types::Generics {
+        self.inner.generics
+    }
+
+    /// e.g. `Clone` in `type Item: Clone;`
+    pub fn bounds(&self) -> Item<'c, &'c [rustdoc_types::GenericBound]> {
+        self.morph(self.inner.bounds)
+    }
+
+    /// The type in an impl, e.g. `usize` in `type X = usize;`
+    pub fn default(&self) -> Option<Item<'c, &'c rustdoc_types::Type>> {
+        self.inner.default.map(|ty| self.morph(ty))
+    }
+}
 
 // </assoc-type>
 